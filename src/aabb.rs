@@ -0,0 +1,71 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+use crate::ray::Ray;
+
+#[derive(Copy,Clone)]
+pub struct Aabb<T: Float+MulAssign> {
+    pub min: Vec3<T>,
+    pub max: Vec3<T>,
+}
+
+impl<T: Float+MulAssign> Aabb<T> {
+    pub fn new(a: Vec3<T>, b: Vec3<T>) -> Aabb<T> {
+        Aabb {
+            min: Vec3::min(&a, &b),
+            max: Vec3::max(&a, &b),
+        }
+    }
+
+    pub fn surrounding_box(a: &Aabb<T>, b: &Aabb<T>) -> Aabb<T> {
+        Aabb {
+            min: Vec3::min(&a.min, &b.min),
+            max: Vec3::max(&a.max, &b.max),
+        }
+    }
+
+    pub fn centroid(&self) -> Vec3<T> {
+        let two = T::one() + T::one();
+        (self.min + self.max) / two
+    }
+
+    pub fn surface_area(&self) -> T {
+        let two = T::one() + T::one();
+        let d = self.max - self.min;
+        two * (d[0]*d[1] + d[1]*d[2] + d[2]*d[0])
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let d = self.max - self.min;
+        if d[0] > d[1] && d[0] > d[2] {
+            0
+        } else if d[1] > d[2] {
+            1
+        } else {
+            2
+        }
+    }
+
+    // slab test, see "Ray Tracing: The Next Week"
+    pub fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> bool {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+
+        for a in 0..3 {
+            let inv_d = T::one() / r.direction[a];
+            let mut t0 = (self.min[a] - r.origin[a]) * inv_d;
+            let mut t1 = (self.max[a] - r.origin[a]) * inv_d;
+            if inv_d < T::zero() {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = if t0 > t_min { t0 } else { t_min };
+            t_max = if t1 < t_max { t1 } else { t_max };
+            if t_max <= t_min {
+                return false;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,227 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+use crate::ray::Ray;
+use crate::aabb::Aabb;
+use crate::hitable::{HitRecord, Hitable, HitResult};
+use crate::material::Material;
+use crate::pdf::{random_float, solid_angle_pdf};
+
+#[derive(Copy,Clone)]
+pub enum Plane {
+    XY,
+    XZ,
+    YZ,
+}
+
+impl Plane {
+    // axes spanning the plane and the axis orthogonal to it
+    fn axes(self) -> (usize, usize, usize) {
+        match self {
+            Plane::XY => (0, 1, 2),
+            Plane::XZ => (0, 2, 1),
+            Plane::YZ => (1, 2, 0),
+        }
+    }
+}
+
+/// Geometry of an axis aligned rectangle spanning [a0, a1] x [b0, b1] on
+/// `plane` at `k` along the remaining axis. The normal points towards
+/// increasing k, or the opposite way if `flip` is set.
+#[derive(Copy,Clone)]
+pub struct Rect<T: Float+MulAssign> {
+    pub plane: Plane,
+    pub a0: T,
+    pub a1: T,
+    pub b0: T,
+    pub b1: T,
+    pub k: T,
+    pub flip: bool,
+}
+
+impl<T: Float+MulAssign> Rect<T> {
+    pub fn xy(x0: T, x1: T, y0: T, y1: T, k: T) -> Rect<T> {
+        Rect { plane: Plane::XY, a0: x0, a1: x1, b0: y0, b1: y1, k, flip: false }
+    }
+
+    pub fn xz(x0: T, x1: T, z0: T, z1: T, k: T) -> Rect<T> {
+        Rect { plane: Plane::XZ, a0: x0, a1: x1, b0: z0, b1: z1, k, flip: false }
+    }
+
+    pub fn yz(y0: T, y1: T, z0: T, z1: T, k: T) -> Rect<T> {
+        Rect { plane: Plane::YZ, a0: y0, a1: y1, b0: z0, b1: z1, k, flip: false }
+    }
+
+    pub fn flipped(mut self) -> Rect<T> {
+        self.flip = !self.flip;
+        self
+    }
+
+    pub fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitRecord<T>> {
+        let (a, b, k) = self.plane.axes();
+
+        let t = (self.k - r.origin[k]) / r.direction[k];
+        // also false if t is NaN, i.e. the ray is parallel to the plane
+        if !(t > t_min && t < t_max) {
+            return None;
+        }
+
+        let p = r.point_at_parameter(t);
+        if p[a] < self.a0 || p[a] > self.a1 || p[b] < self.b0 || p[b] > self.b1 {
+            return None;
+        }
+
+        let mut normal = [T::zero(); 3];
+        normal[k] = if self.flip { -T::one() } else { T::one() };
+
+        Some(HitRecord {
+            t,
+            p,
+            normal: Vec3::new(Some(normal)),
+            u: (p[a] - self.a0) / (self.a1 - self.a0),
+            v: (p[b] - self.b0) / (self.b1 - self.b0),
+            barycentric: None,
+        })
+    }
+
+    pub fn area(&self) -> T {
+        (self.a1 - self.a0) * (self.b1 - self.b0)
+    }
+
+    pub fn random_point(&self) -> Vec3<T> {
+        let (a, b, k) = self.plane.axes();
+        let mut p = [T::zero(); 3];
+        p[a] = self.a0 + random_float::<T>() * (self.a1 - self.a0);
+        p[b] = self.b0 + random_float::<T>() * (self.b1 - self.b0);
+        p[k] = self.k;
+        Vec3::new(Some(p))
+    }
+
+    /// Density of `direction` from `origin` when points are picked
+    /// uniformly on `area`
+    fn pdf_value(&self, origin: &Vec3<T>, direction: &Vec3<T>, area: T) -> T {
+        let r = Ray {
+            origin: *origin,
+            direction: *direction,
+            time: T::zero(),
+            wavelengths: None,
+        };
+        match self.hit(&r, T::from(0.001).unwrap(), T::max_value()) {
+            Some(rec) => solid_angle_pdf(direction, rec.t, &rec.normal, area),
+            None => T::zero(),
+        }
+    }
+
+    pub fn bounding_box(&self) -> Aabb<T> {
+        // padded, the box of a rectangle is flat along k
+        let (a, b, k) = self.plane.axes();
+        let pad = T::from(1e-4).unwrap();
+        let mut min = [T::zero(); 3];
+        let mut max = [T::zero(); 3];
+        min[a] = self.a0;
+        max[a] = self.a1;
+        min[b] = self.b0;
+        max[b] = self.b1;
+        min[k] = self.k - pad;
+        max[k] = self.k + pad;
+        Aabb::new(Vec3::new(Some(min)), Vec3::new(Some(max)))
+    }
+}
+
+pub struct AaRect<T: Float+MulAssign> {
+    pub rect: Rect<T>,
+    pub material: Box<dyn Material<T>>,
+}
+
+impl<T: Float+MulAssign+Send+Sync> Hitable<T> for AaRect<T> {
+    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'_, T>> {
+        Some(HitResult {
+            rec: self.rect.hit(r, t_min, t_max)?,
+            material: self.material.as_ref(),
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        Some(self.rect.bounding_box())
+    }
+
+    fn pdf_value(&self, origin: &Vec3<T>, direction: &Vec3<T>) -> T {
+        self.rect.pdf_value(origin, direction, self.rect.area())
+    }
+
+    fn random(&self, origin: &Vec3<T>) -> Vec3<T> {
+        self.rect.random_point() - *origin
+    }
+}
+
+/// Axis aligned box made of six rectangles with outward normals
+pub struct BoxShape<T: Float+MulAssign> {
+    min: Vec3<T>,
+    max: Vec3<T>,
+    sides: [Rect<T>; 6],
+    material: Box<dyn Material<T>>,
+}
+
+impl<T: Float+MulAssign> BoxShape<T> {
+    pub fn new(p0: Vec3<T>, p1: Vec3<T>, material: Box<dyn Material<T>>) -> BoxShape<T> {
+        let min = Vec3::min(&p0, &p1);
+        let max = Vec3::max(&p0, &p1);
+
+        BoxShape {
+            min,
+            max,
+            sides: [
+                Rect::xy(min[0], max[0], min[1], max[1], max[2]),
+                Rect::xy(min[0], max[0], min[1], max[1], min[2]).flipped(),
+                Rect::xz(min[0], max[0], min[2], max[2], max[1]),
+                Rect::xz(min[0], max[0], min[2], max[2], min[1]).flipped(),
+                Rect::yz(min[1], max[1], min[2], max[2], max[0]),
+                Rect::yz(min[1], max[1], min[2], max[2], min[0]).flipped(),
+            ],
+            material,
+        }
+    }
+}
+
+impl<T: Float+MulAssign+Send+Sync> Hitable<T> for BoxShape<T> {
+    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'_, T>> {
+        let mut closest_so_far = t_max;
+        let mut hit_record: Option<HitRecord<T>> = None;
+
+        for side in &self.sides {
+            if let Some(rec) = side.hit(r, t_min, closest_so_far) {
+                closest_so_far = rec.t;
+                hit_record = Some(rec);
+            }
+        }
+
+        hit_record.map(|rec| HitResult {
+            rec,
+            material: self.material.as_ref(),
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        Some(Aabb::new(self.min, self.max))
+    }
+
+    // points are uniform over the whole surface, so a direction can reach
+    // them through both the front and the back sides
+    fn pdf_value(&self, origin: &Vec3<T>, direction: &Vec3<T>) -> T {
+        let area = self.sides.iter().fold(T::zero(), |a, side| a + side.area());
+        self.sides.iter().fold(T::zero(), |sum, side| sum + side.pdf_value(origin, direction, area))
+    }
+
+    fn random(&self, origin: &Vec3<T>) -> Vec3<T> {
+        let area = self.sides.iter().fold(T::zero(), |a, side| a + side.area());
+        let mut x = random_float::<T>() * area;
+        for side in &self.sides[..5] {
+            if x < side.area() {
+                return side.random_point() - *origin;
+            }
+            x = x - side.area();
+        }
+        self.sides[5].random_point() - *origin
+    }
+}
@@ -0,0 +1,46 @@
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use crate::environment::EnvironmentMap;
+use crate::sky::DaylightSky;
+
+/// Radiance of the rays that escape the world
+pub enum Background {
+    // the white to blue gradient from the book
+    Sky,
+    Color(Vec3<f32>),
+    Environment(Box<EnvironmentMap>),
+    Daylight(Box<DaylightSky>),
+}
+
+impl Background {
+    pub fn color(&self, r: &Ray<f32>) -> Vec3<f32> {
+        match self {
+            Background::Sky => {
+                let unit_direction = Vec3::unit_vector(r.direction);
+                let t = 0.5*(unit_direction.get_y() + 1.0);
+                Vec3::new(Some([1.0, 1.0, 1.0]))*(1.0 - t) + Vec3::new(Some([0.5, 0.7, 1.0]))*t
+            },
+            Background::Color(c) => *c,
+            Background::Environment(e) => e.radiance(&r.direction),
+            Background::Daylight(s) => s.radiance(&r.direction),
+        }
+    }
+
+    /// Random direction towards the background, for backgrounds that are
+    /// worth sampling as lights, with its density
+    pub fn sample(&self) -> Option<(Vec3<f32>, f32)> {
+        match self {
+            Background::Environment(e) => Some(e.sample()),
+            Background::Daylight(s) => Some(s.sample()),
+            _ => None,
+        }
+    }
+
+    pub fn pdf_value(&self, direction: &Vec3<f32>) -> f32 {
+        match self {
+            Background::Environment(e) => e.pdf_value(direction),
+            Background::Daylight(s) => s.pdf_value(direction),
+            _ => 0.0,
+        }
+    }
+}
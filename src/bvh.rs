@@ -0,0 +1,200 @@
+use std::cmp::Ordering;
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::aabb::Aabb;
+use crate::hitable::{Hitable, HitResult};
+use crate::hitablelist::HitableList;
+use crate::ray::Ray;
+
+// nodes with at most this many objects become leaves
+// unless the SAH finds a cheaper split
+const MAX_LEAF_SIZE: usize = 4;
+// cost of traversing a node relative to intersecting an object
+const TRAVERSAL_COST: f64 = 0.125;
+
+type Item<T> = (Aabb<T>, Box<dyn Hitable<T>>);
+
+enum BvhNode<T: Float+MulAssign> {
+    Leaf {
+        bbox: Aabb<T>,
+        objects: Vec<Box<dyn Hitable<T>>>,
+    },
+    Branch {
+        bbox: Aabb<T>,
+        left: Box<BvhNode<T>>,
+        right: Box<BvhNode<T>>,
+    },
+}
+
+/// Bounding volume hierarchy over the objects of a `HitableList`, split
+/// using the surface area heuristic. Objects without a bounding box are
+/// kept aside and tested linearly.
+pub struct Bvh<T: Float+MulAssign> {
+    root: Option<BvhNode<T>>,
+    unbounded: HitableList<T>,
+}
+
+impl<T: Float+MulAssign> Bvh<T> {
+    pub fn new(list: HitableList<T>) -> Bvh<T> {
+        let mut items: Vec<Item<T>> = Vec::with_capacity(list.list.len());
+        let mut unbounded: HitableList<T> = HitableList {
+            list: Vec::new()
+        };
+
+        for h in list.list {
+            match h.bounding_box() {
+                Some(bbox) => items.push((bbox, h)),
+                None => unbounded.list.push(h),
+            }
+        }
+
+        Bvh {
+            root: if items.is_empty() { None } else { Some(BvhNode::build(items)) },
+            unbounded,
+        }
+    }
+}
+
+impl<T: Float+MulAssign> From<HitableList<T>> for Bvh<T> {
+    fn from(list: HitableList<T>) -> Bvh<T> {
+        Bvh::new(list)
+    }
+}
+
+fn sort_by_centroid<T: Float+MulAssign>(items: &mut [Item<T>], axis: usize) {
+    items.sort_by(|a, b| {
+        a.0.centroid()[axis].partial_cmp(&b.0.centroid()[axis]).unwrap_or(Ordering::Equal)
+    });
+}
+
+impl<T: Float+MulAssign> BvhNode<T> {
+    fn build(mut items: Vec<Item<T>>) -> BvhNode<T> {
+        let n = items.len();
+        let mut bbox = items[0].0;
+        for (b, _) in &items[1..] {
+            bbox = Aabb::surrounding_box(&bbox, b);
+        }
+
+        // find the cheapest split along each axis by sweeping the
+        // objects sorted by centroid
+        let parent_area = bbox.surface_area().to_f64().unwrap_or(0.0);
+        let mut best: Option<(usize, usize, f64)> = None;
+        if parent_area > 0.0 && n > 1 {
+            for axis in 0..3 {
+                sort_by_centroid(&mut items, axis);
+
+                let mut right_areas = vec![0.0; n];
+                let mut acc = items[n-1].0;
+                for i in (1..n).rev() {
+                    acc = Aabb::surrounding_box(&acc, &items[i].0);
+                    right_areas[i] = acc.surface_area().to_f64().unwrap_or(0.0);
+                }
+
+                let mut acc = items[0].0;
+                for i in 1..n {
+                    let left_area = acc.surface_area().to_f64().unwrap_or(0.0);
+                    let cost = TRAVERSAL_COST
+                        + (left_area * (i as f64) + right_areas[i] * ((n - i) as f64)) / parent_area;
+                    if best.is_none_or(|(_, _, c)| cost < c) {
+                        best = Some((axis, i, cost));
+                    }
+                    acc = Aabb::surrounding_box(&acc, &items[i].0);
+                }
+            }
+        }
+
+        let (axis, split) = match best {
+            Some((_, _, cost)) if n <= MAX_LEAF_SIZE && cost >= n as f64 => {
+                return BvhNode::Leaf {
+                    bbox,
+                    objects: items.into_iter().map(|(_, h)| h).collect(),
+                };
+            },
+            Some((axis, split, _)) => (axis, split),
+            None if n <= MAX_LEAF_SIZE => {
+                return BvhNode::Leaf {
+                    bbox,
+                    objects: items.into_iter().map(|(_, h)| h).collect(),
+                };
+            },
+            // degenerate bounds, fall back to a median split
+            None => (bbox.longest_axis(), n / 2),
+        };
+
+        sort_by_centroid(&mut items, axis);
+        let right_items = items.split_off(split);
+
+        BvhNode::Branch {
+            bbox,
+            left: Box::new(BvhNode::build(items)),
+            right: Box::new(BvhNode::build(right_items)),
+        }
+    }
+
+    fn bbox(&self) -> &Aabb<T> {
+        match self {
+            BvhNode::Leaf { bbox, .. } => bbox,
+            BvhNode::Branch { bbox, .. } => bbox,
+        }
+    }
+
+    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'_, T>> {
+        if !self.bbox().hit(r, t_min, t_max) {
+            return None;
+        }
+
+        match self {
+            BvhNode::Leaf { objects, .. } => {
+                let mut closest_so_far = t_max;
+                let mut hit_record: Option<HitResult<T>> = None;
+
+                for h in objects {
+                    if let Some(t) = h.hit(r, t_min, closest_so_far) {
+                        closest_so_far = t.rec.t;
+                        hit_record = Some(t);
+                    }
+                }
+
+                hit_record
+            },
+            BvhNode::Branch { left, right, .. } => {
+                let hit_left = left.hit(r, t_min, t_max);
+                let closest_so_far = match &hit_left {
+                    Some(t) => t.rec.t,
+                    None => t_max,
+                };
+                match right.hit(r, t_min, closest_so_far) {
+                    Some(t) => Some(t),
+                    None => hit_left,
+                }
+            },
+        }
+    }
+}
+
+impl<T: Float+MulAssign+Send+Sync> Hitable<T> for Bvh<T> {
+    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'_, T>> {
+        let mut closest_so_far = t_max;
+        let mut hit_record: Option<HitResult<T>> = None;
+
+        if let Some(root) = &self.root {
+            if let Some(t) = root.hit(r, t_min, closest_so_far) {
+                closest_so_far = t.rec.t;
+                hit_record = Some(t);
+            }
+        }
+        if let Some(t) = self.unbounded.hit(r, t_min, closest_so_far) {
+            hit_record = Some(t);
+        }
+
+        hit_record
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        if !self.unbounded.list.is_empty() {
+            return None;
+        }
+        self.root.as_ref().map(|root| *root.bbox())
+    }
+}
@@ -3,6 +3,7 @@ use num_traits::Float;
 
 use crate::vec3::Vec3;
 use crate::ray::Ray;
+use crate::random;
 
 pub struct Camera<T: Float+MulAssign+Neg> {
     pub origin: Vec3<T>,
@@ -12,7 +13,9 @@ pub struct Camera<T: Float+MulAssign+Neg> {
     pub lens_radius: T,
     pub u: Vec3<T>,
     pub v: Vec3<T>,
-    pub w: Vec3<T>,
+    // shutter open and close times
+    pub time0: T,
+    pub time1: T,
 }
 
 impl<T: Float+MulAssign+Neg> Camera<T> {
@@ -35,16 +38,26 @@ impl<T: Float+MulAssign+Neg> Camera<T> {
             vertical: v*two*half_height,
             origin: lookfrom,
             lens_radius,
-            u, v, w,
+            u, v,
+            time0: T::zero(),
+            time1: T::zero(),
         }
     }
 
+    pub fn with_shutter(mut self, time0: T, time1: T) -> Camera<T> {
+        self.time0 = time0;
+        self.time1 = time1;
+        self
+    }
+
     pub fn get_ray(&self, s: T, t: T) -> Ray<T> {
         let rd = Vec3::random_in_unit_sphere() * self.lens_radius;
         let offset = self.u * rd.get_x() + self.v * rd.get_y();
         Ray {
             origin: self.origin + offset,
             direction: self.lower_left_corner + self.horizontal * s + self.vertical * t - self.origin - offset,
+            time: self.time0 + (self.time1 - self.time0) * T::from(random::gen::<f32>()).unwrap(),
+            wavelengths: None,
         }
     }
 }
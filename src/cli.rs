@@ -0,0 +1,138 @@
+use std::path::PathBuf;
+
+use clap::{CommandFactory, Parser};
+use clap::error::ErrorKind;
+
+use crate::vec3::Vec3;
+use crate::scene::Scene;
+
+/// Ray tracer from "Ray Tracing in One Weekend"
+///
+/// Renders the random scene from the book, or the scene described in a
+/// TOML file. Options given on the command line take precedence over the
+/// values in the scene file.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Scene description file
+    pub scene: Option<PathBuf>,
+
+    /// Output image, written to stdout as plain text (P3) PPM if missing.
+    /// The format is picked by extension: png, binary ppm, or the linear
+    /// hdr, pfm and exr
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Store OpenEXR channels as half instead of full floats
+    #[arg(long)]
+    pub half: bool,
+
+    /// Image width in pixels [default: 1200]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub width: Option<u32>,
+
+    /// Image height in pixels [default: 800]
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub height: Option<u32>,
+
+    /// Samples per pixel [default: 50]
+    #[arg(short, long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub samples: Option<u32>,
+
+    /// Trace wavelengths rather than red, green and blue, for dispersion
+    #[arg(long)]
+    pub spectral: bool,
+
+    /// Maximum number of bounces of a ray
+    #[arg(long, default_value_t = 50)]
+    pub max_depth: u32,
+
+    /// Seed of the random number generator, for reproducible renders
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Number of worker threads [default: number of CPUs]
+    #[arg(short = 'j', long, value_parser = clap::value_parser!(u32).range(1..))]
+    pub threads: Option<u32>,
+
+    /// Camera position, as x,y,z
+    #[arg(long, value_parser = parse_vec3, allow_hyphen_values = true)]
+    pub lookfrom: Option<Vec3<f32>>,
+
+    /// Point the camera looks at, as x,y,z
+    #[arg(long, value_parser = parse_vec3, allow_hyphen_values = true)]
+    pub lookat: Option<Vec3<f32>>,
+
+    /// Camera up direction, as x,y,z
+    #[arg(long, value_parser = parse_vec3, allow_hyphen_values = true)]
+    pub vup: Option<Vec3<f32>>,
+
+    /// Vertical field of view in degrees
+    #[arg(long)]
+    pub vfov: Option<f32>,
+
+    /// Lens aperture, 0 for a pinhole camera
+    #[arg(long)]
+    pub aperture: Option<f32>,
+
+    /// Distance of the plane in focus
+    #[arg(long)]
+    pub focus_dist: Option<f32>,
+}
+
+fn parse_vec3(s: &str) -> Result<Vec3<f32>, String> {
+    let e = s.split(',')
+        .map(|x| x.trim().parse::<f32>().map_err(|_| format!("invalid number '{}'", x)))
+        .collect::<Result<Vec<f32>, String>>()?;
+    if e.len() != 3 {
+        return Err(format!("expected x,y,z, found {} values", e.len()));
+    }
+    Ok(Vec3::new(Some([e[0], e[1], e[2]])))
+}
+
+impl Args {
+    /// Exits with a usage error on options that do not apply together
+    pub fn check(&self) {
+        let exr = self.output.as_ref()
+            .and_then(|p| p.extension())
+            .is_some_and(|e| e.eq_ignore_ascii_case("exr"));
+        if self.half && !exr {
+            Args::command().error(ErrorKind::ArgumentConflict, "--half only applies to OpenEXR (.exr) output").exit();
+        }
+    }
+
+    pub fn apply_overrides(&self, scene: &mut Scene) {
+        if let Some(width) = self.width {
+            scene.width = width as usize;
+        }
+        if let Some(height) = self.height {
+            scene.height = height as usize;
+        }
+        if let Some(samples) = self.samples {
+            scene.samples = samples;
+        }
+        if self.spectral {
+            scene.spectral = true;
+        }
+
+        let camera = &mut scene.camera;
+        if let Some(lookfrom) = self.lookfrom {
+            camera.lookfrom = lookfrom;
+        }
+        if let Some(lookat) = self.lookat {
+            camera.lookat = lookat;
+        }
+        if let Some(vup) = self.vup {
+            camera.vup = vup;
+        }
+        if let Some(vfov) = self.vfov {
+            camera.vfov = vfov;
+        }
+        if let Some(aperture) = self.aperture {
+            camera.aperture = aperture;
+        }
+        if let Some(focus_dist) = self.focus_dist {
+            camera.focus_dist = focus_dist;
+        }
+    }
+}
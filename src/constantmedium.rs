@@ -0,0 +1,61 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+use crate::ray::Ray;
+use crate::aabb::Aabb;
+use crate::hitable::{HitRecord, Hitable, HitResult};
+use crate::material::Material;
+use crate::random;
+
+/// Volume of constant density filling a closed, convex `boundary`, e.g.
+/// smoke or fog. Rays scatter inside it after an exponentially distributed
+/// free-flight distance, in the direction picked by `phase_function`.
+pub struct ConstantMedium<T: Float+MulAssign> {
+    pub boundary: Box<dyn Hitable<T>>,
+    pub density: T,
+    pub phase_function: Box<dyn Material<T>>,
+}
+
+impl<T: Float+MulAssign+Send+Sync> Hitable<T> for ConstantMedium<T> {
+    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'_, T>> {
+        // entry and exit points along the whole line, so that rays starting
+        // inside the boundary (e.g. the camera in a fog sphere) still find
+        // the part of the medium in front of them
+        let entry = self.boundary.hit(r, -T::infinity(), T::infinity())?.rec.t;
+        let epsilon = T::from(0.0001).unwrap();
+        let exit = self.boundary.hit(r, entry + epsilon, T::infinity())?.rec.t;
+
+        let t0 = entry.max(t_min).max(T::zero());
+        let t1 = exit.min(t_max);
+        if t0 >= t1 {
+            return None;
+        }
+
+        let length = r.direction.length();
+        let distance_inside = (t1 - t0) * length;
+        let xi = T::from(random::gen::<f32>()).unwrap();
+        let hit_distance = -xi.ln() / self.density;
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = t0 + hit_distance / length;
+        Some(HitResult {
+            rec: HitRecord {
+                t,
+                p: r.point_at_parameter(t),
+                // arbitrary, the phase function does not use it
+                normal: Vec3::new(Some([T::one(), T::zero(), T::zero()])),
+                u: T::zero(),
+                v: T::zero(),
+                barycentric: None,
+            },
+            material: self.phase_function.as_ref(),
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        self.boundary.bounding_box()
+    }
+}
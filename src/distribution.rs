@@ -0,0 +1,141 @@
+/// Piecewise constant distribution over [0, 1) with one bucket per value
+/// of `func`, sampled by inverting its CDF
+pub struct Distribution1D {
+    func: Vec<f32>,
+    cdf: Vec<f32>,
+    integral: f32,
+}
+
+impl Distribution1D {
+    pub fn new(func: Vec<f32>) -> Distribution1D {
+        let n = func.len();
+        let mut cdf = Vec::with_capacity(n + 1);
+        cdf.push(0.0);
+        for (i, f) in func.iter().enumerate() {
+            cdf.push(cdf[i] + f / n as f32);
+        }
+
+        let integral = cdf[n];
+        for (i, c) in cdf.iter_mut().enumerate() {
+            // all zero, buckets are picked uniformly but have no density
+            *c = if integral > 0.0 { *c / integral } else { i as f32 / n as f32 };
+        }
+
+        Distribution1D { func, cdf, integral }
+    }
+
+    pub fn count(&self) -> usize {
+        self.func.len()
+    }
+
+    /// Maps `u` in [0, 1) to a sample, returning it together with its
+    /// density and bucket
+    pub fn sample(&self, u: f32) -> (f32, f32, usize) {
+        // last bucket whose cdf does not exceed u
+        let offset = (self.cdf.partition_point(|&c| c <= u).max(1) - 1).min(self.count() - 1);
+        let width = self.cdf[offset + 1] - self.cdf[offset];
+        let du = if width > 0.0 { (u - self.cdf[offset]) / width } else { 0.0 };
+        let x = ((offset as f32 + du) / self.count() as f32).min(1.0 - f32::EPSILON);
+        (x, self.pdf(offset), offset)
+    }
+
+    /// Density within bucket `i`
+    pub fn pdf(&self, i: usize) -> f32 {
+        if self.integral > 0.0 { self.func[i] / self.integral } else { 0.0 }
+    }
+}
+
+/// Piecewise constant distribution over [0, 1)^2 from a grid of values,
+/// given row by row
+pub struct Distribution2D {
+    rows: Vec<Distribution1D>,
+    marginal: Distribution1D,
+}
+
+impl Distribution2D {
+    pub fn new(func: &[f32], width: usize, height: usize) -> Distribution2D {
+        let rows: Vec<_> = func.chunks(width).take(height)
+            .map(|row| Distribution1D::new(row.to_vec()))
+            .collect();
+        let marginal = Distribution1D::new(rows.iter().map(|r| r.integral).collect());
+        Distribution2D { rows, marginal }
+    }
+
+    /// Picks a row, then a column within it, returning the point as
+    /// (x, y) with its density
+    pub fn sample(&self, u: f32, v: f32) -> ([f32; 2], f32) {
+        let (y, pdf_y, row) = self.marginal.sample(v);
+        let (x, pdf_x, _) = self.rows[row].sample(u);
+        ([x, y], pdf_x * pdf_y)
+    }
+
+    pub fn pdf(&self, x: f32, y: f32) -> f32 {
+        let row = ((y * self.marginal.count() as f32) as usize).min(self.marginal.count() - 1);
+        let r = &self.rows[row];
+        let col = ((x * r.count() as f32) as usize).min(r.count() - 1);
+        if self.marginal.integral > 0.0 { r.func[col] / self.marginal.integral } else { 0.0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // integral of the density over [0, x)
+    fn cdf(d: &Distribution1D, x: f32) -> f32 {
+        let n = d.count() as f32;
+        let i = ((x * n) as usize).min(d.count() - 1);
+        (0..i).map(|j| d.pdf(j) / n).sum::<f32>() + d.pdf(i) * (x - i as f32 / n)
+    }
+
+    #[test]
+    fn density_integrates_to_one() {
+        let d = Distribution1D::new(vec![0.0, 1.0, 3.0, 0.5, 0.0, 2.0]);
+        let integral: f32 = (0..d.count()).map(|i| d.pdf(i)).sum::<f32>() / d.count() as f32;
+        assert!((integral - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sampling_inverts_the_cdf() {
+        let d = Distribution1D::new(vec![0.0, 1.0, 3.0, 0.5, 0.0, 2.0]);
+        for k in 0..100 {
+            let u = k as f32 / 100.0;
+            let (x, pdf, i) = d.sample(u);
+            assert!((cdf(&d, x) - u).abs() < 1e-5, "cdf({}) != {}", x, u);
+            assert_eq!(i, (x * d.count() as f32) as usize);
+            assert_eq!(pdf, d.pdf(i));
+            // empty buckets are never picked
+            assert!(pdf > 0.0);
+        }
+    }
+
+    #[test]
+    fn zero_function_has_no_density() {
+        let d = Distribution1D::new(vec![0.0; 4]);
+        let (x, pdf, _) = d.sample(0.6);
+        assert!((0.0..1.0).contains(&x));
+        assert_eq!(pdf, 0.0);
+    }
+
+    #[test]
+    fn density_2d_integrates_to_one_and_matches_samples() {
+        let (w, h) = (5, 3);
+        let func: Vec<f32> = (0..w * h).map(|i| ((i * 7) % 4) as f32).collect();
+        let d = Distribution2D::new(&func, w, h);
+
+        let mut integral = 0.0;
+        for y in 0..h {
+            for x in 0..w {
+                let (px, py) = ((x as f32 + 0.5) / w as f32, (y as f32 + 0.5) / h as f32);
+                integral += d.pdf(px, py) / (w * h) as f32;
+            }
+        }
+        assert!((integral - 1.0).abs() < 1e-5);
+
+        for k in 0..50 {
+            let (u, v) = ((k as f32 * 0.618_034).fract(), k as f32 / 50.0);
+            let ([x, y], pdf) = d.sample(u, v);
+            assert!((pdf - d.pdf(x, y)).abs() < 1e-5, "({}, {}): {} != {}", x, y, pdf, d.pdf(x, y));
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use std::f32::consts::PI;
+use std::path::Path;
+
+use crate::vec3::Vec3;
+use crate::transform::Transform;
+use crate::distribution::Distribution2D;
+use crate::random;
+
+/// Light from far away, read from an equirectangular image. The centre of
+/// the image is towards -z with +y at the top, `rotation` turns it around
+/// the y axis.
+pub struct EnvironmentMap {
+    width: usize,
+    height: usize,
+    // linear radiance, rows from the top of the image
+    pixels: Vec<Vec3<f32>>,
+    // from world to map directions
+    rotation: Transform<f32>,
+    intensity: f32,
+    // proportional to luminance, for importance sampling
+    distribution: Distribution2D,
+}
+
+fn luminance(c: &Vec3<f32>) -> f32 {
+    0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2]
+}
+
+/// Direction of the point at `uv` in [0, 1)^2 of an equirectangular image
+pub fn equirect_direction(uv: [f32; 2]) -> Vec3<f32> {
+    let phi = (uv[0] - 0.5) * 2.0 * PI;
+    let theta = uv[1] * PI;
+    Vec3::new(Some([theta.sin() * phi.sin(), theta.cos(), -theta.sin() * phi.cos()]))
+}
+
+impl EnvironmentMap {
+    /// Loads a Radiance HDR or OpenEXR image, `rotation` is in degrees
+    pub fn load(path: &Path, rotation: f32, intensity: f32) -> Result<EnvironmentMap, String> {
+        let img = image::open(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?
+            .to_rgb32f();
+        let pixels = img.pixels()
+            .map(|p| Vec3::new(Some([p[0].max(0.0), p[1].max(0.0), p[2].max(0.0)])))
+            .collect();
+
+        Ok(EnvironmentMap::from_pixels(img.width() as usize, img.height() as usize, pixels, rotation, intensity))
+    }
+
+    /// Map from linear radiance, given row by row from the top
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<Vec3<f32>>, rotation: f32, intensity: f32) -> EnvironmentMap {
+        // rows near the poles cover a smaller solid angle
+        let mut func = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let sin_theta = (PI * (y as f32 + 0.5) / height as f32).sin();
+            func.extend(pixels[y * width..(y + 1) * width].iter().map(|p| luminance(p) * sin_theta));
+        }
+
+        EnvironmentMap {
+            width,
+            height,
+            distribution: Distribution2D::new(&func, width, height),
+            pixels,
+            rotation: Transform::rotate_y(-rotation),
+            intensity,
+        }
+    }
+
+    // image coordinates in [0, 1) of a direction, and the sine of its
+    // polar angle
+    fn map_coordinates(&self, direction: &Vec3<f32>) -> ([f32; 2], f32) {
+        let d = Vec3::unit_vector(self.rotation.vector(direction));
+        let phi = d[0].atan2(-d[2]);
+        let theta = d[1].clamp(-1.0, 1.0).acos();
+        let u = (0.5 + phi / (2.0 * PI)).rem_euclid(1.0);
+        ([u, theta / PI], theta.sin())
+    }
+
+    fn map_direction(&self, uv: [f32; 2]) -> Vec3<f32> {
+        self.rotation.inverse().vector(&equirect_direction(uv))
+    }
+
+    pub fn radiance(&self, direction: &Vec3<f32>) -> Vec3<f32> {
+        // nearest texel, so that radiance and density match exactly
+        let ([u, v], _) = self.map_coordinates(direction);
+        let x = ((u * self.width as f32) as usize).min(self.width - 1);
+        let y = ((v * self.height as f32) as usize).min(self.height - 1);
+        self.pixels[y * self.width + x] * self.intensity
+    }
+
+    /// Random direction, picked proportionally to luminance, and its
+    /// density with respect to solid angle
+    pub fn sample(&self) -> (Vec3<f32>, f32) {
+        let (uv, pdf) = self.distribution.sample(random::gen::<f32>(), random::gen::<f32>());
+        let sin_theta = (uv[1] * PI).sin();
+        if sin_theta <= 0.0 {
+            return (self.map_direction(uv), 0.0);
+        }
+        (self.map_direction(uv), pdf / (2.0 * PI * PI * sin_theta))
+    }
+
+    pub fn pdf_value(&self, direction: &Vec3<f32>) -> f32 {
+        let ([u, v], sin_theta) = self.map_coordinates(direction);
+        if sin_theta <= 0.0 {
+            return 0.0;
+        }
+        self.distribution.pdf(u, v) / (2.0 * PI * PI * sin_theta)
+    }
+}
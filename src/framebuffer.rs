@@ -0,0 +1,45 @@
+use crate::vec3::Vec3;
+
+/// Linear radiance accumulated per pixel. Rows go from the top of the
+/// image to the bottom.
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    sums: Vec<Vec3<f32>>,
+    samples: Vec<u32>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            sums: vec![Vec3::new(None); width * height],
+            samples: vec![0; width * height],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Adds `n` samples whose radiance sums to `sum` to the pixel at (x, y)
+    pub fn accumulate(&mut self, x: usize, y: usize, sum: Vec3<f32>, n: u32) {
+        let idx = y * self.width + x;
+        self.sums[idx] += sum;
+        self.samples[idx] += n;
+    }
+
+    /// Average radiance of the pixel at (x, y)
+    pub fn pixel(&self, x: usize, y: usize) -> Vec3<f32> {
+        let idx = y * self.width + x;
+        if self.samples[idx] == 0 {
+            return Vec3::new(None);
+        }
+        self.sums[idx] / (self.samples[idx] as f32)
+    }
+}
@@ -1,14 +1,22 @@
 use std::ops::MulAssign;
+use std::sync::Arc;
 use num_traits::Float;
 
 use crate::vec3::Vec3;
 use crate::ray::Ray;
+use crate::aabb::Aabb;
 use crate::material::Material;
 
 pub struct HitRecord<T: Float+MulAssign> {
     pub t: T,
     pub p: Vec3<T>,
     pub normal: Vec3<T>,
+    // surface coordinates of the hit point. On triangles these are the
+    // barycentric coordinates unless the mesh has texture coordinates.
+    pub u: T,
+    pub v: T,
+    // barycentric coordinates of v1 and v2 on triangles, None elsewhere
+    pub barycentric: Option<[T; 2]>,
 }
 
 pub struct HitResult<'a, T: Float+MulAssign> {
@@ -16,8 +24,43 @@ pub struct HitResult<'a, T: Float+MulAssign> {
     pub material: &'a dyn Material<T>,
 }
 
-pub trait Hitable<T: Float+MulAssign> {
-    fn hit(&self, _r: &Ray<T>, _t_min: T, _t_max: T) -> Option<HitResult<T>> {
+pub trait Hitable<T: Float+MulAssign>: Send + Sync {
+    fn hit(&self, _r: &Ray<T>, _t_min: T, _t_max: T) -> Option<HitResult<'_, T>> {
         None
     }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        None
+    }
+
+    /// Density, with respect to solid angle, of the directions from
+    /// `origin` returned by `random`. Only objects that can be sampled as
+    /// lights implement it.
+    fn pdf_value(&self, _origin: &Vec3<T>, _direction: &Vec3<T>) -> T {
+        T::zero()
+    }
+
+    /// Random direction from `origin` towards a point of the object
+    fn random(&self, _origin: &Vec3<T>) -> Vec3<T> {
+        Vec3::new(Some([T::one(), T::zero(), T::zero()]))
+    }
+}
+
+// lets the same object be both in the world and in the list of lights
+impl<T: Float+MulAssign, H: Hitable<T> + ?Sized> Hitable<T> for Arc<H> {
+    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'_, T>> {
+        (**self).hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        (**self).bounding_box()
+    }
+
+    fn pdf_value(&self, origin: &Vec3<T>, direction: &Vec3<T>) -> T {
+        (**self).pdf_value(origin, direction)
+    }
+
+    fn random(&self, origin: &Vec3<T>) -> Vec3<T> {
+        (**self).random(origin)
+    }
 }
@@ -3,13 +3,16 @@ use num_traits::Float;
 
 use crate::hitable::{Hitable, HitResult};
 use crate::ray::Ray;
+use crate::aabb::Aabb;
+use crate::vec3::Vec3;
+use crate::random;
 
 pub struct HitableList<T: Float+MulAssign> {
     pub list: Vec<Box<dyn Hitable<T>>>,
 }
 
-impl<T: Float+MulAssign> Hitable<T> for HitableList<T> {
-    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<T>> {
+impl<T: Float+MulAssign+Send+Sync> Hitable<T> for HitableList<T> {
+    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'_, T>> {
         let mut closest_so_far = t_max;
         let mut hit_record: Option<HitResult<T>> = None;
 
@@ -22,4 +25,50 @@ impl<T: Float+MulAssign> Hitable<T> for HitableList<T> {
 
         hit_record
     }
-}
\ No newline at end of file
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        let mut result: Option<Aabb<T>> = None;
+
+        for h in &self.list {
+            let bbox = h.bounding_box()?;
+            result = match result {
+                Some(r) => Some(Aabb::surrounding_box(&r, &bbox)),
+                None => Some(bbox),
+            };
+        }
+
+        result
+    }
+
+    // every object is picked with the same probability. An empty list
+    // cannot be sampled, like objects that are not lights.
+    fn pdf_value(&self, origin: &Vec3<T>, direction: &Vec3<T>) -> T {
+        if self.list.is_empty() {
+            return T::zero();
+        }
+        let sum = self.list.iter().fold(T::zero(), |sum, h| sum + h.pdf_value(origin, direction));
+        sum / T::from(self.list.len()).unwrap()
+    }
+
+    fn random(&self, origin: &Vec3<T>) -> Vec3<T> {
+        if self.list.is_empty() {
+            return Vec3::new(Some([T::one(), T::zero(), T::zero()]));
+        }
+        let i = ((random::gen::<f32>() * self.list.len() as f32) as usize).min(self.list.len() - 1);
+        self.list[i].random(origin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_list_has_zero_density() {
+        let list: HitableList<f32> = HitableList { list: Vec::new() };
+        let origin = Vec3::new(None);
+        let direction = list.random(&origin);
+        assert!(direction.e.iter().all(|x| x.is_finite()));
+        assert_eq!(list.pdf_value(&origin, &direction), 0.0);
+    }
+}
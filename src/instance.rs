@@ -0,0 +1,90 @@
+use std::ops::MulAssign;
+use std::sync::Arc;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+use crate::ray::Ray;
+use crate::aabb::Aabb;
+use crate::hitable::{HitRecord, Hitable, HitResult};
+use crate::transform::Transform;
+
+/// Places a shared object in the world through an affine transform from
+/// object to world space. Many instances can share the same object.
+pub struct Instance<T: Float+MulAssign> {
+    object: Arc<dyn Hitable<T>>,
+    transform: Transform<T>,
+    bbox: Option<Aabb<T>>,
+}
+
+impl<T: Float+MulAssign> Instance<T> {
+    pub fn new(object: Arc<dyn Hitable<T>>, transform: Transform<T>) -> Instance<T> {
+        // box around the transformed corners of the object box
+        let bbox = object.bounding_box().map(|b| {
+            let mut result: Option<Aabb<T>> = None;
+            for i in 0..8 {
+                let corner = Vec3::new(Some([
+                    if i & 1 == 0 { b.min[0] } else { b.max[0] },
+                    if i & 2 == 0 { b.min[1] } else { b.max[1] },
+                    if i & 4 == 0 { b.min[2] } else { b.max[2] },
+                ]));
+                let p = transform.point(&corner);
+                result = Some(match result {
+                    Some(r) => Aabb::surrounding_box(&r, &Aabb::new(p, p)),
+                    None => Aabb::new(p, p),
+                });
+            }
+            result.unwrap()
+        });
+
+        Instance {
+            object,
+            transform,
+            bbox,
+        }
+    }
+}
+
+impl<T: Float+MulAssign+Send+Sync> Hitable<T> for Instance<T> {
+    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'_, T>> {
+        // the direction is not normalized, so t is the same in both spaces
+        let inv = self.transform.inverse();
+        let moved = Ray {
+            origin: inv.point(&r.origin),
+            direction: inv.vector(&r.direction),
+            time: r.time,
+            wavelengths: r.wavelengths,
+        };
+
+        let result = self.object.hit(&moved, t_min, t_max)?;
+        Some(HitResult {
+            rec: HitRecord {
+                t: result.rec.t,
+                p: self.transform.point(&result.rec.p),
+                normal: Vec3::unit_vector(self.transform.normal(&result.rec.normal)),
+                u: result.rec.u,
+                v: result.rec.v,
+                barycentric: result.rec.barycentric,
+            },
+            material: result.material,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        self.bbox
+    }
+
+    fn pdf_value(&self, origin: &Vec3<T>, direction: &Vec3<T>) -> T {
+        let inv = self.transform.inverse();
+        let d = inv.vector(direction);
+        let value = self.object.pdf_value(&inv.point(origin), &d);
+        // non-uniform scales distort solid angles, for w' = Aw / |Aw| the
+        // ratio dw' / dw is |det A| / |Aw|^3 with w of unit length
+        let ratio = (direction.length() / d.length()).powi(3);
+        value * inv.determinant().abs() * ratio
+    }
+
+    fn random(&self, origin: &Vec3<T>) -> Vec3<T> {
+        let inv = self.transform.inverse();
+        self.transform.vector(&self.object.random(&inv.point(origin)))
+    }
+}
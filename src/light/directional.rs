@@ -0,0 +1,22 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+use crate::light::{Light, LightSample};
+
+/// Light from infinitely far away travelling along `direction`, e.g. the
+/// sun. `irradiance` is in W/m^2 on a surface facing the light.
+pub struct DirectionalLight<T: Float+MulAssign> {
+    pub direction: Vec3<T>,
+    pub irradiance: Vec3<T>,
+}
+
+impl<T: Float+MulAssign+Send+Sync> Light<T> for DirectionalLight<T> {
+    fn sample(&self, _p: &Vec3<T>) -> Option<LightSample<T>> {
+        Some(LightSample {
+            direction: -Vec3::unit_vector(self.direction),
+            distance: T::infinity(),
+            irradiance: self.irradiance,
+        })
+    }
+}
@@ -0,0 +1,29 @@
+mod point;
+mod spot;
+mod directional;
+
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+
+pub use self::point::PointLight;
+pub use self::spot::SpotLight;
+pub use self::directional::DirectionalLight;
+
+/// Light arriving at a point from a light without geometry
+pub struct LightSample<T: Float+MulAssign> {
+    // unit vector from the point towards the light
+    pub direction: Vec3<T>,
+    // how far shadow rays have to go, infinite for directional lights
+    pub distance: T,
+    // irradiance on a surface facing the light, W/m^2
+    pub irradiance: Vec3<T>,
+}
+
+/// Point, spot and directional lights. They cannot be hit by rays and
+/// are only found through shadow rays.
+pub trait Light<T: Float+MulAssign>: Send + Sync {
+    /// Light reaching `p`, None if `p` is outside of its reach
+    fn sample(&self, p: &Vec3<T>) -> Option<LightSample<T>>;
+}
@@ -0,0 +1,28 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+use crate::light::{Light, LightSample};
+
+/// Light emitting in all directions from `position`, `intensity` is the
+/// radiant intensity in W/sr, i.e. its power divided by 4 pi
+pub struct PointLight<T: Float+MulAssign> {
+    pub position: Vec3<T>,
+    pub intensity: Vec3<T>,
+}
+
+impl<T: Float+MulAssign+Send+Sync> Light<T> for PointLight<T> {
+    fn sample(&self, p: &Vec3<T>) -> Option<LightSample<T>> {
+        let d = self.position - *p;
+        let distance = d.length();
+        if distance <= T::zero() {
+            return None;
+        }
+
+        Some(LightSample {
+            direction: d / distance,
+            distance,
+            irradiance: self.intensity / (distance * distance),
+        })
+    }
+}
@@ -0,0 +1,55 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+use crate::light::{Light, LightSample};
+
+/// Point light restricted to a cone around `direction`. The intensity,
+/// in W/sr, is full within `inner_angle` of the axis and falls smoothly
+/// to zero at `outer_angle`, both half angles in degrees.
+pub struct SpotLight<T: Float+MulAssign> {
+    pub position: Vec3<T>,
+    pub direction: Vec3<T>,
+    pub intensity: Vec3<T>,
+    pub inner_angle: T,
+    pub outer_angle: T,
+}
+
+impl<T: Float+MulAssign> SpotLight<T> {
+    fn falloff(&self, cos_theta: T) -> T {
+        let cos_inner = self.inner_angle.to_radians().cos();
+        let cos_outer = self.outer_angle.to_radians().cos();
+        if cos_theta >= cos_inner {
+            return T::one();
+        }
+        if cos_theta <= cos_outer {
+            return T::zero();
+        }
+        // smoothstep between the two cones
+        let x = (cos_theta - cos_outer) / (cos_inner - cos_outer);
+        x * x * (T::from(3.0).unwrap() - (T::one() + T::one()) * x)
+    }
+}
+
+impl<T: Float+MulAssign+Send+Sync> Light<T> for SpotLight<T> {
+    fn sample(&self, p: &Vec3<T>) -> Option<LightSample<T>> {
+        let d = self.position - *p;
+        let distance = d.length();
+        if distance <= T::zero() {
+            return None;
+        }
+        let direction = d / distance;
+
+        let cos_theta = -Vec3::dot(&direction, &Vec3::unit_vector(self.direction));
+        let falloff = self.falloff(cos_theta);
+        if falloff <= T::zero() {
+            return None;
+        }
+
+        Some(LightSample {
+            direction,
+            distance,
+            irradiance: self.intensity * (falloff / (distance * distance)),
+        })
+    }
+}
@@ -0,0 +1,111 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::ray::Ray;
+use crate::hitable::HitRecord;
+use crate::vec3::Vec3;
+use crate::material::{Material, ScatterResult};
+use crate::pdf::Pdf;
+use crate::texture::Texture;
+use crate::spectrum;
+
+use super::microfacet::{Ggx, GgxReflectionPdf, viewer_frame, roughness_at};
+use super::utils::{reflect, fresnel_conductor};
+
+/// Metal with a GGX microfacet surface, reflecting according to the
+/// complex refractive index eta + ik for red, green and blue light
+pub struct Conductor<T: Float+MulAssign> {
+    pub eta: Vec3<T>,
+    pub k: Vec3<T>,
+    // perceptual roughness, see `roughness_at`
+    pub roughness: Box<dyn Texture<T>>,
+}
+
+impl Conductor<f32> {
+    /// eta and k of measured metals, for 650, 550 and 450 nm
+    pub fn preset(name: &str) -> Option<(Vec3<f32>, Vec3<f32>)> {
+        let (eta, k) = match name {
+            "gold" => ([0.183, 0.421, 1.373], [3.424, 2.346, 1.770]),
+            "copper" => ([0.271, 0.677, 1.316], [3.609, 2.625, 2.292]),
+            "aluminium" | "aluminum" => ([1.657, 0.880, 0.521], [9.224, 6.270, 4.837]),
+            "silver" => ([0.159, 0.145, 0.135], [3.929, 3.190, 2.381]),
+            _ => return None,
+        };
+        Some((Vec3::new(Some(eta)), Vec3::new(Some(k))))
+    }
+
+    fn ggx(&self, hr: &HitRecord<f32>) -> Ggx {
+        Ggx::from_roughness(roughness_at(self.roughness.as_ref(), hr))
+    }
+
+    // reflectance at the wavelengths of `r`, upsampled from the three
+    // channels of eta and k
+    fn fresnel(&self, r: &Ray<f32>, cosine: f32) -> Vec3<f32> {
+        spectrum::reflectance(&r.wavelengths, fresnel_conductor(cosine, &self.eta, &self.k))
+    }
+}
+
+impl Material<f32> for Conductor<f32> {
+    fn scatter(&self, r: &Ray<f32>, hr: &HitRecord<f32>) -> Option<ScatterResult<f32>> {
+        let (uvw, wo, _) = viewer_frame(r, hr);
+        if wo[2] <= 0.0 {
+            return None;
+        }
+        let ggx = self.ggx(hr);
+
+        if ggx.is_smooth() {
+            return Some(ScatterResult {
+                attenuation: self.fresnel(r, wo[2]),
+                scattered: Ray {
+                    origin: hr.p,
+                    direction: uvw.local(&reflect(-wo, Vec3::new(Some([0.0, 0.0, 1.0])))),
+                    time: r.time,
+                    wavelengths: r.wavelengths,
+                },
+                pdf: None,
+            });
+        }
+
+        let pdf = GgxReflectionPdf { uvw, wo, ggx };
+        let direction = pdf.generate();
+        let wi = Vec3::unit_vector(pdf.uvw.components(&direction));
+        // reflections off microfacets can go below the surface, those are
+        // absorbed but the lights are still sampled
+        let attenuation = if wi[2] > 0.0 {
+            // D cancels out of eval / pdf
+            let h = Vec3::unit_vector(wo + wi);
+            self.fresnel(r, Vec3::dot(&wo, &h)) * (ggx.g(&wo, &wi) / ggx.g1(&wo))
+        } else {
+            Vec3::new(None)
+        };
+
+        Some(ScatterResult {
+            attenuation,
+            scattered: Ray {
+                origin: hr.p,
+                direction,
+                time: r.time,
+                wavelengths: r.wavelengths,
+            },
+            pdf: Some(Box::new(pdf)),
+        })
+    }
+
+    fn eval(&self, r: &Ray<f32>, hr: &HitRecord<f32>, scattered: &Ray<f32>) -> Vec3<f32> {
+        let zero = Vec3::new(None);
+        let ggx = self.ggx(hr);
+        if ggx.is_smooth() {
+            return zero;
+        }
+        let (uvw, wo, _) = viewer_frame(r, hr);
+        let wi = Vec3::unit_vector(uvw.components(&scattered.direction));
+        if wo[2] <= 0.0 || wi[2] <= 0.0 {
+            return zero;
+        }
+
+        // D G F / (4 cos_o cos_i), times cos_i
+        let h = Vec3::unit_vector(wo + wi);
+        let f = self.fresnel(r, Vec3::dot(&wo, &h));
+        f * (ggx.d(&h) * ggx.g(&wo, &wi) / (4.0 * wo[2]))
+    }
+}
@@ -1,49 +1,59 @@
 use std::ops::MulAssign;
 use num_traits::Float;
-use rand::Rng;
 
 use crate::ray::Ray;
 use crate::hitable::HitRecord;
 use crate::vec3::Vec3;
-use crate::material::{Material, ScatterResult};
+use crate::material::{Material, ScatterResult, Dispersion};
+use crate::random;
 
-use super::utils::{reflect, refract, schlick};
+use super::utils::{reflect, refract, schlick, transmittance, refractive_index};
 
 pub struct Dielectric<T: Float+MulAssign> {
     pub ref_idx: T,
+    // absorption coefficient per unit length for red, green and blue
+    // light, applied to rays leaving the medium for the distance they
+    // travelled inside
+    pub absorption: Vec3<T>,
+    // replaces `ref_idx` in spectral mode
+    pub dispersion: Option<Dispersion>,
 }
 
 impl Material<f32> for Dielectric<f32> {
     fn scatter(&self, r: &Ray<f32>, hr: &HitRecord<f32>) -> Option<ScatterResult<f32>> {
         let reflected = reflect(r.direction, hr.normal);
+        let (ref_idx, weight, wavelengths) = refractive_index(self.ref_idx, &self.dispersion, r);
 
-        let attenuation = Vec3::new(Some([1.0, 1.0, 1.0]));
+        let mut attenuation = weight;
 
         let mut outward_normal = hr.normal;
-        let mut ni_over_nt = 1.0 / self.ref_idx;
+        let mut ni_over_nt = 1.0 / ref_idx;
         let mut cosine = -Vec3::dot(&r.direction, &hr.normal) / r.direction.length();
         if Vec3::dot(&r.direction, &hr.normal) > 0.0 {
             outward_normal = -hr.normal;
-            ni_over_nt = self.ref_idx;
-            cosine *= -self.ref_idx;
+            ni_over_nt = ref_idx;
+            cosine *= -ref_idx;
+            attenuation *= transmittance(r, hr, &self.absorption);
         }
 
         let mut reflect_prob = 1.0;
         let mut refracted: Vec3<f32> = Vec3::new(None);
-        if let Some(r) = refract(r.direction, outward_normal, ni_over_nt) {
-            reflect_prob = schlick(cosine, self.ref_idx);
-            refracted = r;
+        if let Some(rd) = refract(r.direction, outward_normal, ni_over_nt) {
+            reflect_prob = schlick(cosine, ref_idx);
+            refracted = rd;
         }
 
-        let mut rng = rand::thread_rng();
 
-        if rng.gen::<f32>() < reflect_prob {
+        if random::gen::<f32>() < reflect_prob {
             Some(ScatterResult {
                 attenuation,
                 scattered: Ray {
                     origin: hr.p,
                     direction: reflected,
+                    time: r.time,
+                    wavelengths,
                 },
+                pdf: None,
             })
         } else {
             Some(ScatterResult {
@@ -51,7 +61,10 @@ impl Material<f32> for Dielectric<f32> {
                 scattered: Ray {
                     origin: hr.p,
                     direction: refracted,
+                    time: r.time,
+                    wavelengths,
                 },
+                pdf: None,
             })
         }
     }
@@ -0,0 +1,21 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::ray::Ray;
+use crate::hitable::HitRecord;
+use crate::vec3::Vec3;
+use crate::material::{Material, ScatterResult};
+
+pub struct DiffuseLight<T: Float+MulAssign> {
+    pub emit: Vec3<T>,
+}
+
+impl Material<f32> for DiffuseLight<f32> {
+    fn scatter(&self, _r: &Ray<f32>, _hr: &HitRecord<f32>) -> Option<ScatterResult<f32>> {
+        None
+    }
+
+    fn emitted(&self, _u: f32, _v: f32, _p: &Vec3<f32>) -> Vec3<f32> {
+        self.emit
+    }
+}
@@ -0,0 +1,89 @@
+/// Index of refraction that varies with the wavelength, for dielectrics
+/// in spectral mode
+#[derive(Clone, Copy)]
+pub enum Dispersion {
+    /// n = a + b / lambda^2, with lambda in micrometres
+    Cauchy { a: f32, b: f32 },
+    /// n^2 = 1 + sum of b_i lambda^2 / (lambda^2 - c_i), with lambda in
+    /// micrometres
+    Sellmeier { b: [f32; 3], c: [f32; 3] },
+}
+
+// wavelength of the Fraunhofer d line in nm, where catalogues give the
+// index of glasses
+const D_LINE: f32 = 587.6;
+
+impl Dispersion {
+    /// Measured glasses and gems
+    pub fn preset(name: &str) -> Option<Dispersion> {
+        let (b, c) = match name {
+            "bk7" => ([1.039_612, 0.231_792_34, 1.010_469_5], [0.006_000_699, 0.020_017_914, 103.560_65]),
+            "fused_silica" => ([0.696_166_3, 0.407_942_6, 0.897_479_4], [0.004_679_148, 0.013_512_06, 97.934]),
+            "sf11" => ([1.737_596_9, 0.313_747_35, 1.898_781], [0.013_188_707, 0.062_306_814, 155.236_3]),
+            "diamond" => ([4.3356, 0.3306, 0.0], [0.011_236, 0.030_625, 0.0]),
+            _ => return None,
+        };
+        Some(Dispersion::Sellmeier { b, c })
+    }
+
+    /// Index at `lambda` nm
+    pub fn ior(&self, lambda: f32) -> f32 {
+        let l2 = (lambda / 1000.0).powi(2);
+        match self {
+            Dispersion::Cauchy { a, b } => a + b / l2,
+            Dispersion::Sellmeier { b, c } => {
+                let mut n2 = 1.0;
+                for (b, c) in b.iter().zip(c.iter()) {
+                    n2 += b * l2 / (l2 - c);
+                }
+                n2.max(1.0).sqrt()
+            },
+        }
+    }
+
+    /// Index used when rendering in RGB
+    pub fn nominal_ior(&self) -> f32 {
+        self.ior(D_LINE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // index at the d line and Abbe number from the catalogues
+    const PRESETS: [(&str, f32, f32); 4] = [
+        ("bk7", 1.5168, 64.17),
+        ("fused_silica", 1.4585, 67.82),
+        ("sf11", 1.7847, 25.68),
+        ("diamond", 2.4175, 55.3),
+    ];
+
+    #[test]
+    fn presets_match_their_catalogue_values() {
+        for &(name, nd, abbe) in &PRESETS {
+            let d = Dispersion::preset(name).unwrap();
+            assert!((d.nominal_ior() - nd).abs() < 1e-3, "{}: {} != {}", name, d.nominal_ior(), nd);
+            // between the Fraunhofer F and C lines
+            let v = (d.ior(D_LINE) - 1.0) / (d.ior(486.1) - d.ior(656.3));
+            assert!((v - abbe).abs() < 1.0, "{}: Abbe number {} != {}", name, v, abbe);
+        }
+        assert!(Dispersion::preset("glass").is_none());
+    }
+
+    #[test]
+    fn index_falls_across_the_visible_range() {
+        for &(name, _, _) in &PRESETS {
+            let d = Dispersion::preset(name).unwrap();
+            for lambda in (380..780).step_by(10) {
+                assert!(d.ior(lambda as f32) > d.ior(lambda as f32 + 10.0), "{} at {} nm", name, lambda);
+            }
+        }
+    }
+
+    #[test]
+    fn cauchy_index() {
+        let d = Dispersion::Cauchy { a: 1.5, b: 0.01 };
+        assert!((d.ior(500.0) - 1.54).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,38 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::ray::Ray;
+use crate::hitable::HitRecord;
+use crate::vec3::Vec3;
+use crate::material::{Material, ScatterResult};
+use crate::pdf::{Pdf, SpherePdf};
+use crate::texture::Texture;
+use crate::spectrum;
+
+/// Phase function of participating media, scatters uniformly in all
+/// directions
+pub struct Isotropic<T: Float+MulAssign> {
+    pub albedo: Box<dyn Texture<T>>,
+}
+
+impl Material<f32> for Isotropic<f32> {
+    fn scatter(&self, r: &Ray<f32>, hr: &HitRecord<f32>) -> Option<ScatterResult<f32>> {
+        let scattered = Ray {
+            origin: hr.p,
+            direction: Pdf::<f32>::generate(&SpherePdf),
+            time: r.time,
+            wavelengths: r.wavelengths,
+        };
+        let attenuation = spectrum::reflectance(&r.wavelengths, self.albedo.value(hr.u, hr.v, &hr.p));
+
+        Some(ScatterResult { attenuation, scattered, pdf: Some(Box::new(SpherePdf)) })
+    }
+
+    fn eval(&self, r: &Ray<f32>, hr: &HitRecord<f32>, scattered: &Ray<f32>) -> Vec3<f32> {
+        spectrum::reflectance(&r.wavelengths, self.albedo.value(hr.u, hr.v, &hr.p)) * self.scattering_pdf(r, hr, scattered)
+    }
+
+    fn scattering_pdf(&self, _r: &Ray<f32>, _hr: &HitRecord<f32>, _scattered: &Ray<f32>) -> f32 {
+        1.0 / (4.0 * std::f32::consts::PI)
+    }
+}
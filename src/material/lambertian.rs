@@ -5,20 +5,38 @@ use crate::ray::Ray;
 use crate::hitable::HitRecord;
 use crate::vec3::Vec3;
 use crate::material::{Material, ScatterResult};
+use crate::pdf::{Pdf, CosinePdf};
+use crate::texture::Texture;
+use crate::spectrum;
 
 pub struct Lambertian<T: Float+MulAssign> {
-    pub albedo: Vec3<T>,
+    pub albedo: Box<dyn Texture<T>>,
 }
 
 impl Material<f32> for Lambertian<f32> {
-    fn scatter(&self, _r: &Ray<f32>, hr: &HitRecord<f32>) -> Option<ScatterResult<f32>> {
-        let target = hr.p + hr.normal + Vec3::random_in_unit_sphere();
+    fn scatter(&self, r: &Ray<f32>, hr: &HitRecord<f32>) -> Option<ScatterResult<f32>> {
+        let pdf = CosinePdf::new(&hr.normal);
         let scattered = Ray {
             origin: hr.p,
-            direction: target - hr.p,
+            direction: pdf.generate(),
+            time: r.time,
+            wavelengths: r.wavelengths,
         };
-        let attenuation = self.albedo;
+        let attenuation = spectrum::reflectance(&r.wavelengths, self.albedo.value(hr.u, hr.v, &hr.p));
 
-        Some(ScatterResult { attenuation, scattered })
+        Some(ScatterResult { attenuation, scattered, pdf: Some(Box::new(pdf)) })
     }
-}
\ No newline at end of file
+
+    fn eval(&self, r: &Ray<f32>, hr: &HitRecord<f32>, scattered: &Ray<f32>) -> Vec3<f32> {
+        let pdf = self.scattering_pdf(r, hr, scattered);
+        if pdf <= 0.0 {
+            return Vec3::new(None);
+        }
+        spectrum::reflectance(&r.wavelengths, self.albedo.value(hr.u, hr.v, &hr.p)) * pdf
+    }
+
+    fn scattering_pdf(&self, _r: &Ray<f32>, hr: &HitRecord<f32>, scattered: &Ray<f32>) -> f32 {
+        let cosine = Vec3::dot(&hr.normal, &Vec3::unit_vector(scattered.direction));
+        if cosine > 0.0 { cosine / std::f32::consts::PI } else { 0.0 }
+    }
+}
@@ -5,33 +5,92 @@ use crate::ray::Ray;
 use crate::hitable::HitRecord;
 use crate::vec3::Vec3;
 use crate::material::{Material, ScatterResult};
+use crate::pdf::Pdf;
+use crate::texture::Texture;
+use crate::spectrum;
 
 use super::utils::reflect;
 
 pub struct Metal<T: Float+MulAssign> {
-    pub albedo: Vec3<T>,
+    pub albedo: Box<dyn Texture<T>>,
     pub fuzz: T,
 }
 
+/// Directions towards a random point of the ball of radius `fuzz` around
+/// the tip of the unit `reflected` vector
+struct FuzzPdf {
+    reflected: Vec3<f32>,
+    fuzz: f32,
+}
+
+impl Pdf<f32> for FuzzPdf {
+    fn value(&self, direction: &Vec3<f32>) -> f32 {
+        // the ball density integrated along the direction, between the
+        // points where it enters and leaves the ball
+        let b = Vec3::dot(&Vec3::unit_vector(*direction), &self.reflected);
+        let discriminant = b*b - (1.0 - self.fuzz*self.fuzz);
+        if discriminant <= 0.0 {
+            return 0.0;
+        }
+        let t1 = (b - discriminant.sqrt()).max(0.0);
+        let t2 = b + discriminant.sqrt();
+        if t2 <= 0.0 {
+            return 0.0;
+        }
+        (t2.powi(3) - t1.powi(3)) / (4.0 * std::f32::consts::PI * self.fuzz.powi(3))
+    }
+
+    fn generate(&self) -> Vec3<f32> {
+        self.reflected + Vec3::random_in_unit_sphere() * self.fuzz
+    }
+}
+
+impl Metal<f32> {
+    fn fuzz_pdf(&self, r: &Ray<f32>, hr: &HitRecord<f32>) -> FuzzPdf {
+        FuzzPdf {
+            reflected: reflect(Vec3::unit_vector(r.direction), hr.normal),
+            fuzz: self.fuzz.min(1.0),
+        }
+    }
+}
+
 impl Material<f32> for Metal<f32> {
     fn scatter(&self, r: &Ray<f32>, hr: &HitRecord<f32>) -> Option<ScatterResult<f32>> {
-        let f = if self.fuzz < 1.0 {
-            self.fuzz
-        } else {
-            1.0
-        };
+        let attenuation = spectrum::reflectance(&r.wavelengths, self.albedo.value(hr.u, hr.v, &hr.p));
 
-        let reflected = reflect(Vec3::unit_vector(r.direction), hr.normal);
+        // a perfect mirror is specular
+        let pdf = if self.fuzz > 0.0 { Some(self.fuzz_pdf(r, hr)) } else { None };
+        let direction = match &pdf {
+            Some(pdf) => pdf.generate(),
+            None => reflect(Vec3::unit_vector(r.direction), hr.normal),
+        };
         let scattered = Ray {
             origin: hr.p,
-            direction: reflected + Vec3::random_in_unit_sphere() * f,
+            direction,
+            time: r.time,
+            wavelengths: r.wavelengths,
         };
-        let attenuation = self.albedo;
 
-        if Vec3::dot(&scattered.direction, &hr.normal) <= 0.0 {
+        // absorbed below the surface, rough reflections still get light
+        // sampling
+        let below = Vec3::dot(&scattered.direction, &hr.normal) <= 0.0;
+        if below && pdf.is_none() {
             return None;
         }
 
-        Some(ScatterResult { attenuation, scattered })
+        Some(ScatterResult {
+            attenuation: if below { Vec3::new(None) } else { attenuation },
+            scattered,
+            pdf: pdf.map(|p| Box::new(p) as Box<dyn Pdf<f32>>),
+        })
+    }
+
+    // the albedo times the sampling density, directions below the surface
+    // are absorbed
+    fn eval(&self, r: &Ray<f32>, hr: &HitRecord<f32>, scattered: &Ray<f32>) -> Vec3<f32> {
+        if self.fuzz <= 0.0 || Vec3::dot(&scattered.direction, &hr.normal) <= 0.0 {
+            return Vec3::new(None);
+        }
+        spectrum::reflectance(&r.wavelengths, self.albedo.value(hr.u, hr.v, &hr.p)) * self.fuzz_pdf(r, hr).value(&scattered.direction)
     }
 }
@@ -0,0 +1,330 @@
+use std::f32::consts::PI;
+
+use crate::ray::Ray;
+use crate::hitable::HitRecord;
+use crate::vec3::Vec3;
+use crate::onb::Onb;
+use crate::pdf::Pdf;
+use crate::texture::Texture;
+use crate::random;
+
+use super::utils::{reflect, refract, fresnel_dielectric};
+
+// below this alpha surfaces are treated as perfectly smooth
+const SMOOTH_ALPHA: f32 = 1e-3;
+
+/// Local frame around the normal of the hit on the side of the viewer,
+/// with the local direction towards the viewer, and whether the ray hit
+/// the back of the surface
+pub fn viewer_frame(r: &Ray<f32>, hr: &HitRecord<f32>) -> (Onb<f32>, Vec3<f32>, bool) {
+    let back = Vec3::dot(&r.direction, &hr.normal) > 0.0;
+    let uvw = Onb::from_w(&if back { -hr.normal } else { hr.normal });
+    let wo = Vec3::unit_vector(uvw.components(&-r.direction));
+    (uvw, wo, back)
+}
+
+/// Perceptual roughness at the hit, read from the first channel of
+/// `roughness` and clamped to [0, 1]
+pub fn roughness_at(roughness: &dyn Texture<f32>, hr: &HitRecord<f32>) -> f32 {
+    roughness.value(hr.u, hr.v, &hr.p)[0].clamp(0.0, 1.0)
+}
+
+/// Trowbridge-Reitz (GGX) distribution of microfacet normals with Smith
+/// masking-shadowing. Directions are given in a local frame where the
+/// macro surface normal is +z, and alpha_x and alpha_y are the roughness
+/// along x and y.
+#[derive(Clone, Copy)]
+pub struct Ggx {
+    pub alpha_x: f32,
+    pub alpha_y: f32,
+}
+
+impl Ggx {
+    /// Isotropic distribution, alpha is the square of the perceptual
+    /// roughness in [0, 1]
+    pub fn from_roughness(roughness: f32) -> Ggx {
+        let alpha = roughness.clamp(0.0, 1.0).powi(2);
+        Ggx { alpha_x: alpha, alpha_y: alpha }
+    }
+
+    /// Distribution rougher along x than along y by `anisotropic` in
+    /// [0, 1], mapped as in the Disney BRDF. Alpha is kept above the
+    /// threshold of smooth surfaces, so that this is never specular.
+    pub fn anisotropic(roughness: f32, anisotropic: f32) -> Ggx {
+        let alpha = roughness.clamp(0.0, 1.0).powi(2);
+        let aspect = (1.0 - 0.9 * anisotropic.clamp(0.0, 1.0)).sqrt();
+        Ggx {
+            alpha_x: (alpha / aspect).max(SMOOTH_ALPHA),
+            alpha_y: (alpha * aspect).max(SMOOTH_ALPHA),
+        }
+    }
+
+    /// Smooth surfaces reflect and refract in single directions, which
+    /// must be handled as specular scattering
+    pub fn is_smooth(&self) -> bool {
+        self.alpha_x.max(self.alpha_y) < SMOOTH_ALPHA
+    }
+
+    /// Density of microfacet normals `h`, per unit of projected area
+    pub fn d(&self, h: &Vec3<f32>) -> f32 {
+        if h[2] <= 0.0 {
+            return 0.0;
+        }
+        let e = (h[0] / self.alpha_x).powi(2) + (h[1] / self.alpha_y).powi(2) + h[2] * h[2];
+        1.0 / (PI * self.alpha_x * self.alpha_y * e * e)
+    }
+
+    fn lambda(&self, w: &Vec3<f32>) -> f32 {
+        let cos2 = w[2] * w[2];
+        if cos2 <= 0.0 {
+            return f32::INFINITY;
+        }
+        let alpha2_tan2 = ((w[0] * self.alpha_x).powi(2) + (w[1] * self.alpha_y).powi(2)) / cos2;
+        0.5 * (-1.0 + (1.0 + alpha2_tan2).sqrt())
+    }
+
+    /// Fraction of the microfacets visible from `w`
+    pub fn g1(&self, w: &Vec3<f32>) -> f32 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Fraction of the microfacets visible from both `wo` and `wi`, with
+    /// correlated masking and shadowing
+    pub fn g(&self, wo: &Vec3<f32>, wi: &Vec3<f32>) -> f32 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Microfacet normal visible from `wo`, which must be above the
+    /// surface, from "Sampling the GGX Distribution of Visible Normals" by
+    /// Heitz
+    pub fn sample_visible(&self, wo: &Vec3<f32>) -> Vec3<f32> {
+        let (u1, u2) = (random::gen::<f32>(), random::gen::<f32>());
+
+        // the view direction on the hemisphere of the stretched distribution
+        let vh = Vec3::unit_vector(Vec3::new(Some([self.alpha_x * wo[0], self.alpha_y * wo[1], wo[2]])));
+        let lensq = vh[0] * vh[0] + vh[1] * vh[1];
+        let t1 = if lensq > 0.0 {
+            Vec3::new(Some([-vh[1], vh[0], 0.0])) / lensq.sqrt()
+        } else {
+            Vec3::new(Some([1.0, 0.0, 0.0]))
+        };
+        let t2 = Vec3::cross(&vh, &t1);
+
+        // a point on the disc, squeezed onto the visible half
+        let r = u1.sqrt();
+        let phi = 2.0 * PI * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh[2]);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).max(0.0).sqrt() + s * r * phi.sin();
+        let nh = t1 * p1 + t2 * p2 + vh * (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt();
+
+        Vec3::unit_vector(Vec3::new(Some([self.alpha_x * nh[0], self.alpha_y * nh[1], nh[2].max(1e-6)])))
+    }
+
+    /// Density of `sample_visible` picking `h`
+    pub fn visible_pdf(&self, wo: &Vec3<f32>, h: &Vec3<f32>) -> f32 {
+        if wo[2] <= 0.0 {
+            return 0.0;
+        }
+        self.g1(wo) * Vec3::dot(wo, h).max(0.0) * self.d(h) / wo[2]
+    }
+}
+
+/// Reflections off visible microfacets, `wo` is the local direction
+/// towards the viewer
+pub struct GgxReflectionPdf {
+    pub uvw: Onb<f32>,
+    pub wo: Vec3<f32>,
+    pub ggx: Ggx,
+}
+
+impl Pdf<f32> for GgxReflectionPdf {
+    fn value(&self, direction: &Vec3<f32>) -> f32 {
+        let wi = Vec3::unit_vector(self.uvw.components(direction));
+        if wi[2] <= 0.0 {
+            return 0.0;
+        }
+        let h = Vec3::unit_vector(self.wo + wi);
+        let cosine = Vec3::dot(&self.wo, &h);
+        if cosine <= 0.0 {
+            return 0.0;
+        }
+        // the reflection doubles the angles around h
+        self.ggx.visible_pdf(&self.wo, &h) / (4.0 * cosine)
+    }
+
+    fn generate(&self) -> Vec3<f32> {
+        let h = self.ggx.sample_visible(&self.wo);
+        self.uvw.local(&reflect(-self.wo, h))
+    }
+}
+
+/// Reflections and refractions off visible microfacets, picked according
+/// to their Fresnel reflectance. `wo` is the local direction towards the
+/// viewer and `eta` the index of the far side over the index of its side.
+pub struct GgxDielectricPdf {
+    pub uvw: Onb<f32>,
+    pub wo: Vec3<f32>,
+    pub eta: f32,
+    pub ggx: Ggx,
+}
+
+// microfacet normal between `wo` and `wi`, facing `wo`, together with
+// whether the pair is a reflection
+fn dielectric_half_vector(wo: &Vec3<f32>, wi: &Vec3<f32>, eta: f32) -> Option<(Vec3<f32>, bool)> {
+    let reflection = wi[2] > 0.0;
+    let h = if reflection { *wo + *wi } else { *wo + *wi * eta };
+    if h.squared_length() <= 0.0 {
+        return None;
+    }
+    let h = Vec3::unit_vector(h);
+    let h = if h[2] < 0.0 { -h } else { h };
+    // microfacets seen from behind cannot scatter
+    if Vec3::dot(wo, &h) <= 0.0 || (Vec3::dot(wi, &h) > 0.0) != reflection {
+        return None;
+    }
+    Some((h, reflection))
+}
+
+impl GgxDielectricPdf {
+    /// Local direction, and whether it was reflected
+    pub fn sample(&self) -> (Vec3<f32>, bool) {
+        let h = self.ggx.sample_visible(&self.wo);
+        let cosine = Vec3::dot(&self.wo, &h);
+        if random::gen::<f32>() < fresnel_dielectric(cosine, self.eta) {
+            return (reflect(-self.wo, h), true);
+        }
+        match refract(-self.wo, h, 1.0 / self.eta) {
+            Some(wi) => (Vec3::unit_vector(wi), false),
+            // not reached, total internal reflection has a reflectance of one
+            None => (reflect(-self.wo, h), true),
+        }
+    }
+}
+
+impl Pdf<f32> for GgxDielectricPdf {
+    fn value(&self, direction: &Vec3<f32>) -> f32 {
+        let wi = Vec3::unit_vector(self.uvw.components(direction));
+        let (h, reflection) = match dielectric_half_vector(&self.wo, &wi, self.eta) {
+            Some(h) => h,
+            None => return 0.0,
+        };
+        let cos_o = Vec3::dot(&self.wo, &h);
+        let cos_i = Vec3::dot(&wi, &h);
+        let f = fresnel_dielectric(cos_o, self.eta);
+        let visible = self.ggx.visible_pdf(&self.wo, &h);
+        if reflection {
+            f * visible / (4.0 * cos_o)
+        } else {
+            // change of variables from the microfacet normal to the
+            // refracted direction
+            let denominator = cos_i + cos_o / self.eta;
+            (1.0 - f) * visible * cos_i.abs() / (denominator * denominator)
+        }
+    }
+
+    fn generate(&self) -> Vec3<f32> {
+        self.uvw.local(&self.sample().0)
+    }
+}
+
+/// BSDF of "Microfacet Models for Refraction through Rough Surfaces" by
+/// Walter et al. times cos_i, for local directions and the relative index
+/// `eta` as for `GgxDielectricPdf`
+pub fn dielectric_bsdf(ggx: &Ggx, wo: &Vec3<f32>, wi: &Vec3<f32>, eta: f32) -> f32 {
+    if wo[2] <= 0.0 {
+        return 0.0;
+    }
+    let (h, reflection) = match dielectric_half_vector(wo, wi, eta) {
+        Some(h) => h,
+        None => return 0.0,
+    };
+    let cos_o = Vec3::dot(wo, &h);
+    let cos_i = Vec3::dot(wi, &h);
+    let f = fresnel_dielectric(cos_o, eta);
+    let dg = ggx.d(&h) * ggx.g(wo, wi);
+    if reflection {
+        f * dg / (4.0 * wo[2])
+    } else {
+        let denominator = cos_i + cos_o / eta;
+        (1.0 - f) * dg * (cos_i * cos_o).abs() / (wo[2] * denominator * denominator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // midpoint rule over the hemisphere around +z
+    fn integrate(f: impl Fn(&Vec3<f32>) -> f32) -> f32 {
+        let (n_theta, n_phi) = (1000, 200);
+        let (d_theta, d_phi) = (0.5 * PI / n_theta as f32, 2.0 * PI / n_phi as f32);
+        let mut sum = 0.0f64;
+        for i in 0..n_theta {
+            let theta = (i as f32 + 0.5) * d_theta;
+            for j in 0..n_phi {
+                let phi = (j as f32 + 0.5) * d_phi;
+                let w = Vec3::new(Some([theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()]));
+                sum += (f(&w) * theta.sin() * d_theta * d_phi) as f64;
+            }
+        }
+        sum as f32
+    }
+
+    fn distributions() -> Vec<Ggx> {
+        vec![
+            Ggx::from_roughness(0.5),
+            Ggx::from_roughness(1.0),
+            Ggx::anisotropic(0.6, 0.8),
+        ]
+    }
+
+    #[test]
+    fn projected_microfacet_area_is_one() {
+        for ggx in distributions() {
+            let area = integrate(|h| ggx.d(h) * h[2]);
+            assert!((area - 1.0).abs() < 1e-2, "({}, {}): {}", ggx.alpha_x, ggx.alpha_y, area);
+        }
+    }
+
+    #[test]
+    fn visible_microfacets_project_to_the_macro_surface() {
+        for ggx in distributions() {
+            for &wo in &[[0.0, 0.0, 1.0], [0.6, 0.0, 0.8], [0.3, -0.5, 0.2]] {
+                let wo = Vec3::unit_vector(Vec3::new(Some(wo)));
+                let visible = integrate(|h| ggx.visible_pdf(&wo, h));
+                assert!((visible - 1.0).abs() < 1e-2, "({}, {}) {:?}: {}", ggx.alpha_x, ggx.alpha_y, wo.e, visible);
+            }
+        }
+    }
+
+    #[test]
+    fn normal_incidence_is_unmasked() {
+        for ggx in distributions() {
+            assert_eq!(ggx.g1(&Vec3::new(Some([0.0, 0.0, 1.0]))), 1.0);
+            assert!(ggx.g1(&Vec3::new(Some([0.9, 0.0, 0.1]))) < 1.0);
+        }
+    }
+
+    #[test]
+    fn dielectric_pdf_covers_its_samples() {
+        let wo = Vec3::unit_vector(Vec3::new(Some([0.5, 0.2, 0.7])));
+        for &eta in &[1.5, 1.0 / 1.5] {
+            let pdf = GgxDielectricPdf {
+                uvw: Onb::from_w(&Vec3::new(Some([0.0, 0.0, 1.0]))),
+                wo,
+                eta,
+                ggx: Ggx::from_roughness(0.7),
+            };
+            // reflections below the surface and refractions above it are
+            // absorbed by `RoughDielectric`, the rest must be covered exactly
+            let n = 100_000;
+            let kept = (0..n).filter(|_| {
+                let (wi, reflected) = pdf.sample();
+                (wi[2] > 0.0) == reflected
+            }).count();
+            let total = integrate(|wi| pdf.value(wi)) + integrate(|wi| pdf.value(&Vec3::new(Some([wi[0], wi[1], -wi[2]]))));
+            assert!((total - kept as f32 / n as f32).abs() < 1e-2, "eta {}: {} != {}", eta, total, kept);
+        }
+    }
+}
@@ -1,7 +1,14 @@
 mod lambertian;
 mod metal;
 mod dielectric;
+mod diffuselight;
+mod isotropic;
+mod conductor;
+mod roughdielectric;
+mod principled;
+mod dispersion;
 
+mod microfacet;
 mod utils;
 
 use std::ops::MulAssign;
@@ -10,16 +17,102 @@ use num_traits::Float;
 use crate::ray::Ray;
 use crate::hitable::HitRecord;
 use crate::vec3::Vec3;
+use crate::pdf::Pdf;
 
 pub use self::lambertian::Lambertian;
 pub use self::metal::Metal;
 pub use self::dielectric::Dielectric;
+pub use self::diffuselight::DiffuseLight;
+pub use self::isotropic::Isotropic;
+pub use self::conductor::Conductor;
+pub use self::roughdielectric::RoughDielectric;
+pub use self::principled::Principled;
+pub use self::dispersion::Dispersion;
 
 pub struct ScatterResult<T: Float+MulAssign> {
+    // weight of the light arriving along `scattered`, that is eval / pdf
+    // when the scattering is not specular
     pub attenuation: Vec3<T>,
     pub scattered: Ray<T>,
+    // density `scattered` was sampled from, the renderer weighs it against
+    // the densities of light sampling. None when the
+    // material scatters in a single direction, which must be followed.
+    pub pdf: Option<Box<dyn Pdf<T>>>,
 }
 
-pub trait Material<T: Float+MulAssign> {
+impl<T: Float+MulAssign> ScatterResult<T> {
+    /// Specular scattering, e.g. by mirrors and glass, cannot be evaluated
+    /// for other directions and so bypasses light sampling
+    pub fn is_specular(&self) -> bool {
+        self.pdf.is_none()
+    }
+}
+
+pub trait Material<T: Float+MulAssign>: Send + Sync {
     fn scatter(&self, r: &Ray<T>, hr: &HitRecord<T>) -> Option<ScatterResult<T>>;
+
+    /// BSDF times the cosine between `scattered` and the normal, for
+    /// directions that were not picked by `scatter` itself. The light
+    /// leaving along `r` is eval * incoming light / its pdf.
+    fn eval(&self, _r: &Ray<T>, _hr: &HitRecord<T>, _scattered: &Ray<T>) -> Vec3<T> {
+        Vec3::new(None)
+    }
+
+    /// `eval` divided by the albedo, for materials whose BSDF is an albedo
+    /// times a density, which they return. Others have no such density,
+    /// the default is the mean of `eval` over the channels.
+    fn scattering_pdf(&self, r: &Ray<T>, hr: &HitRecord<T>, scattered: &Ray<T>) -> T {
+        let f = self.eval(r, hr, scattered);
+        (f[0] + f[1] + f[2]) / T::from(3.0).unwrap()
+    }
+
+    fn emitted(&self, _u: T, _v: T, _p: &Vec3<T>) -> Vec3<T> {
+        Vec3::new(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::texture::ConstantTexture;
+
+    // a BSDF that is not an albedo times a density
+    struct Tinted;
+
+    impl Material<f32> for Tinted {
+        fn scatter(&self, _r: &Ray<f32>, _hr: &HitRecord<f32>) -> Option<ScatterResult<f32>> {
+            None
+        }
+
+        fn eval(&self, _r: &Ray<f32>, _hr: &HitRecord<f32>, _scattered: &Ray<f32>) -> Vec3<f32> {
+            Vec3::new(Some([0.1, 0.2, 0.6]))
+        }
+    }
+
+    #[test]
+    fn scattering_pdf_is_eval_over_the_albedo() {
+        let hr = HitRecord {
+            t: 1.0,
+            p: Vec3::new(None),
+            normal: Vec3::new(Some([0.0, 0.0, 1.0])),
+            u: 0.0,
+            v: 0.0,
+            barycentric: None,
+        };
+        let ray = |direction| Ray {
+            origin: Vec3::new(None),
+            direction: Vec3::new(Some(direction)),
+            time: 0.0,
+            wavelengths: None,
+        };
+        let (r, scattered) = (ray([0.0, 0.6, -0.8]), ray([0.0, -0.6, 0.8]));
+
+        let lambertian = Lambertian { albedo: Box::new(ConstantTexture { color: Vec3::new(Some([0.5, 0.25, 0.0])) }) };
+        let pdf = lambertian.scattering_pdf(&r, &hr, &scattered);
+        assert!((pdf - 0.8 / std::f32::consts::PI).abs() < 1e-6);
+        let f = lambertian.eval(&r, &hr, &scattered);
+        assert!((f[0] - 0.5 * pdf).abs() < 1e-6 && (f[1] - 0.25 * pdf).abs() < 1e-6 && f[2] == 0.0);
+
+        assert!((Tinted.scattering_pdf(&r, &hr, &scattered) - 0.3).abs() < 1e-6);
+    }
 }
@@ -0,0 +1,278 @@
+use std::ops::MulAssign;
+use std::f32::consts::PI;
+use num_traits::Float;
+
+use crate::ray::Ray;
+use crate::hitable::HitRecord;
+use crate::vec3::Vec3;
+use crate::onb::Onb;
+use crate::material::{Material, ScatterResult};
+use crate::pdf::{Pdf, CosinePdf, MixturePdf};
+use crate::texture::Texture;
+use crate::spectrum;
+use crate::random;
+
+use super::microfacet::{Ggx, GgxReflectionPdf, GgxDielectricPdf, dielectric_bsdf, viewer_frame, roughness_at};
+use super::utils::{reflect, schlick};
+
+/// Principled BSDF from "Physically Based Shading at Disney" by Burley and
+/// its extension to transmission, a single material covering plastics,
+/// metals, cloth, varnished and glassy surfaces. Scalar parameters are in
+/// [0, 1] and read from the first channel of their textures.
+pub struct Principled<T: Float+MulAssign> {
+    pub base_color: Box<dyn Texture<T>>,
+    // blends from dielectric to metal, whose reflections take the base colour
+    pub metallic: Box<dyn Texture<T>>,
+    pub roughness: Box<dyn Texture<T>>,
+    // reflectance of dielectrics at normal incidence, 0.08 times this
+    pub specular: Box<dyn Texture<T>>,
+    // tints the reflections of dielectrics towards the base colour
+    pub specular_tint: Box<dyn Texture<T>>,
+    // rougher along the u axis of the local frame, which is the world x
+    // axis projected on the surface except on surfaces facing along x
+    pub anisotropic: Box<dyn Texture<T>>,
+    // grazing reflections of cloth, and their tint towards the base colour
+    pub sheen: Box<dyn Texture<T>>,
+    pub sheen_tint: Box<dyn Texture<T>>,
+    // second, white specular lobe of a clear varnish, and its glossiness
+    pub clearcoat: Box<dyn Texture<T>>,
+    pub clearcoat_gloss: Box<dyn Texture<T>>,
+    // fraction of the dielectric part that is rough glass rather than
+    // diffuse
+    pub transmission: Box<dyn Texture<T>>,
+    // index of refraction of the transmitting part
+    pub ior: T,
+}
+
+// (1 - cosine)^5, the angular falloff of Schlick's Fresnel approximation
+fn schlick_weight(cosine: f32) -> f32 {
+    (1.0 - cosine).clamp(0.0, 1.0).powi(5)
+}
+
+fn luminance(c: &Vec3<f32>) -> f32 {
+    0.3 * c[0] + 0.6 * c[1] + 0.1 * c[2]
+}
+
+fn lerp(a: Vec3<f32>, b: Vec3<f32>, t: f32) -> Vec3<f32> {
+    a * (1.0 - t) + b * t
+}
+
+// Berry distribution (GTR1) of the clearcoat
+fn gtr1(cosine: f32, alpha: f32) -> f32 {
+    let a2 = alpha * alpha;
+    (a2 - 1.0) / (PI * a2.ln() * (1.0 + (a2 - 1.0) * cosine * cosine))
+}
+
+/// Reflections off clearcoat microfacets picked from GTR1
+struct ClearcoatPdf {
+    uvw: Onb<f32>,
+    wo: Vec3<f32>,
+    alpha: f32,
+}
+
+impl Pdf<f32> for ClearcoatPdf {
+    fn value(&self, direction: &Vec3<f32>) -> f32 {
+        let wi = Vec3::unit_vector(self.uvw.components(direction));
+        if wi[2] <= 0.0 {
+            return 0.0;
+        }
+        let h = Vec3::unit_vector(self.wo + wi);
+        let cosine = Vec3::dot(&self.wo, &h);
+        if cosine <= 0.0 {
+            return 0.0;
+        }
+        gtr1(h[2], self.alpha) * h[2] / (4.0 * cosine)
+    }
+
+    fn generate(&self) -> Vec3<f32> {
+        let a2 = self.alpha * self.alpha;
+        let cos2 = (1.0 - a2.powf(1.0 - random::gen::<f32>())) / (1.0 - a2);
+        let (cos_theta, sin_theta) = (cos2.sqrt(), (1.0 - cos2).max(0.0).sqrt());
+        let phi = 2.0 * PI * random::gen::<f32>();
+        let h = Vec3::new(Some([sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta]));
+        self.uvw.local(&reflect(-self.wo, h))
+    }
+}
+
+// the parameters at a hit, in the local frame around the normal on the
+// side of the viewer. Colours are at the wavelengths of the ray.
+struct Lobes {
+    uvw: Onb<f32>,
+    wo: Vec3<f32>,
+    // index of the far side over the index of the side of the viewer
+    eta: f32,
+    ggx: Ggx,
+    roughness: f32,
+    base: Vec3<f32>,
+    // reflectance of the specular lobe at normal incidence
+    specular: Vec3<f32>,
+    sheen: Vec3<f32>,
+    // tint of each crossing of the glass
+    transmitted: Vec3<f32>,
+    diffuse_weight: f32,
+    specular_weight: f32,
+    transmission_weight: f32,
+    clearcoat_weight: f32,
+    clearcoat_alpha: f32,
+    // probabilities of sampling the diffuse, specular, glass and
+    // clearcoat lobes
+    probabilities: [f32; 4],
+}
+
+impl Lobes {
+    // BSDF times cos_i, for the local direction `wi`
+    fn eval(&self, wi: &Vec3<f32>) -> Vec3<f32> {
+        let wo = &self.wo;
+        let mut f = Vec3::new(None);
+
+        if wi[2] > 0.0 {
+            let h = Vec3::unit_vector(*wo + *wi);
+            let cos_d = Vec3::dot(wi, &h);
+
+            if self.diffuse_weight > 0.0 {
+                // retroreflection at grazing angles on rough surfaces
+                let fd90 = 0.5 + 2.0 * self.roughness * cos_d * cos_d;
+                let fl = 1.0 + (fd90 - 1.0) * schlick_weight(wi[2]);
+                let fv = 1.0 + (fd90 - 1.0) * schlick_weight(wo[2]);
+                let diffuse = self.base * (fl * fv / PI) + self.sheen * schlick_weight(cos_d);
+                f += diffuse * (self.diffuse_weight * wi[2]);
+            }
+            if self.specular_weight > 0.0 {
+                let fresnel = lerp(self.specular, Vec3::new(Some([1.0, 1.0, 1.0])), schlick_weight(cos_d));
+                let dg = self.ggx.d(&h) * self.ggx.g(wo, wi);
+                f += fresnel * (self.specular_weight * dg / (4.0 * wo[2]));
+            }
+            if self.clearcoat_weight > 0.0 {
+                let coat = Ggx { alpha_x: 0.25, alpha_y: 0.25 };
+                let dg = gtr1(h[2], self.clearcoat_alpha) * coat.g(wo, wi);
+                let value = self.clearcoat_weight * schlick(cos_d, 1.5) * dg / (4.0 * wo[2]);
+                f += Vec3::new(Some([value, value, value]));
+            }
+        }
+
+        if self.transmission_weight > 0.0 {
+            let value = self.transmission_weight * dielectric_bsdf(&self.ggx, wo, wi, self.eta);
+            f += if wi[2] < 0.0 { self.transmitted * value } else { Vec3::new(Some([value, value, value])) };
+        }
+        f
+    }
+
+    fn pdf(&self) -> MixturePdf<f32> {
+        let normal = self.uvw.w;
+        let frame = || Onb::from_w(&normal);
+        let mut lobes: Vec<(f32, Box<dyn Pdf<f32>>)> = Vec::new();
+        let [diffuse, specular, glass, clearcoat] = self.probabilities;
+        if diffuse > 0.0 {
+            lobes.push((diffuse, Box::new(CosinePdf::new(&normal))));
+        }
+        if specular > 0.0 {
+            lobes.push((specular, Box::new(GgxReflectionPdf { uvw: frame(), wo: self.wo, ggx: self.ggx })));
+        }
+        if glass > 0.0 {
+            lobes.push((glass, Box::new(GgxDielectricPdf { uvw: frame(), wo: self.wo, eta: self.eta, ggx: self.ggx })));
+        }
+        if clearcoat > 0.0 {
+            lobes.push((clearcoat, Box::new(ClearcoatPdf { uvw: frame(), wo: self.wo, alpha: self.clearcoat_alpha })));
+        }
+        MixturePdf { pdfs: lobes }
+    }
+}
+
+impl Principled<f32> {
+    // None when the ray grazes the surface
+    fn lobes(&self, r: &Ray<f32>, hr: &HitRecord<f32>) -> Option<Lobes> {
+        let (uvw, wo, back) = viewer_frame(r, hr);
+        let eta = if back { 1.0 / self.ior } else { self.ior };
+        if wo[2] <= 0.0 {
+            return None;
+        }
+
+        // every scalar parameter is read like the roughness
+        let scalar = |t: &dyn Texture<f32>| roughness_at(t, hr);
+        let base = self.base_color.value(hr.u, hr.v, &hr.p);
+        let metallic = scalar(self.metallic.as_ref());
+        let roughness = roughness_at(self.roughness.as_ref(), hr);
+        let transmission = scalar(self.transmission.as_ref());
+        let sheen = scalar(self.sheen.as_ref());
+        let clearcoat = scalar(self.clearcoat.as_ref());
+
+        // the hue of the base colour
+        let white = Vec3::new(Some([1.0, 1.0, 1.0]));
+        let lum = luminance(&base);
+        let tint = if lum > 0.0 { base / lum } else { white };
+        let dielectric = lerp(white, tint, scalar(self.specular_tint.as_ref())) * (0.08 * scalar(self.specular.as_ref()));
+        let specular = lerp(dielectric, base, metallic);
+        let sheen_color = lerp(white, tint, scalar(self.sheen_tint.as_ref())) * sheen;
+        let transmitted = Vec3::new(Some([base[0].max(0.0).sqrt(), base[1].max(0.0).sqrt(), base[2].max(0.0).sqrt()]));
+
+        let diffuse_weight = (1.0 - metallic) * (1.0 - transmission);
+        let transmission_weight = (1.0 - metallic) * transmission;
+        let specular_weight = 1.0 - transmission_weight;
+        let clearcoat_weight = 0.25 * clearcoat;
+
+        // roughly in proportion to the light each lobe reflects
+        let specular_albedo = luminance(&lerp(specular, white, schlick_weight(wo[2])));
+        let mut probabilities = [
+            diffuse_weight * (lum + sheen),
+            specular_weight * specular_albedo,
+            transmission_weight,
+            clearcoat_weight,
+        ];
+        let total: f32 = probabilities.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        for p in probabilities.iter_mut() {
+            *p /= total;
+        }
+
+        let w = &r.wavelengths;
+        Some(Lobes {
+            uvw,
+            wo,
+            eta,
+            ggx: Ggx::anisotropic(roughness, scalar(self.anisotropic.as_ref())),
+            roughness,
+            base: spectrum::reflectance(w, base),
+            specular: spectrum::reflectance(w, specular),
+            sheen: spectrum::reflectance(w, sheen_color),
+            transmitted: spectrum::reflectance(w, transmitted),
+            diffuse_weight,
+            specular_weight,
+            transmission_weight,
+            clearcoat_weight,
+            clearcoat_alpha: 0.1 + (0.001 - 0.1) * scalar(self.clearcoat_gloss.as_ref()),
+            probabilities,
+        })
+    }
+}
+
+impl Material<f32> for Principled<f32> {
+    fn scatter(&self, r: &Ray<f32>, hr: &HitRecord<f32>) -> Option<ScatterResult<f32>> {
+        let lobes = self.lobes(r, hr)?;
+        let pdf = lobes.pdf();
+        let direction = pdf.generate();
+        // no lobe is specular, the weight is eval / pdf of the mixture
+        let wi = Vec3::unit_vector(lobes.uvw.components(&direction));
+        let pdf_value = pdf.value(&direction);
+        let attenuation = if pdf_value > 0.0 { lobes.eval(&wi) / pdf_value } else { Vec3::new(None) };
+
+        Some(ScatterResult {
+            attenuation,
+            scattered: Ray {
+                origin: hr.p,
+                direction,
+                time: r.time,
+                wavelengths: r.wavelengths,
+            },
+            pdf: Some(Box::new(pdf)),
+        })
+    }
+
+    fn eval(&self, r: &Ray<f32>, hr: &HitRecord<f32>, scattered: &Ray<f32>) -> Vec3<f32> {
+        match self.lobes(r, hr) {
+            Some(lobes) => lobes.eval(&Vec3::unit_vector(lobes.uvw.components(&scattered.direction))),
+            None => Vec3::new(None),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::ray::Ray;
+use crate::hitable::HitRecord;
+use crate::vec3::Vec3;
+use crate::material::{Material, ScatterResult, Dielectric, Dispersion};
+use crate::texture::Texture;
+
+use super::microfacet::{Ggx, GgxDielectricPdf, dielectric_bsdf, viewer_frame, roughness_at};
+use super::utils::{transmittance, refractive_index};
+
+/// Frosted glass, a dielectric with a GGX microfacet surface that both
+/// reflects and transmits. Smooth parts scatter like `Dielectric`.
+pub struct RoughDielectric<T: Float+MulAssign> {
+    pub ref_idx: T,
+    // absorption coefficient per unit length, as for `Dielectric`
+    pub absorption: Vec3<T>,
+    // replaces `ref_idx` in spectral mode, as for `Dielectric`
+    pub dispersion: Option<Dispersion>,
+    // perceptual roughness, see `roughness_at`
+    pub roughness: Box<dyn Texture<T>>,
+}
+
+impl RoughDielectric<f32> {
+    fn ggx(&self, hr: &HitRecord<f32>) -> Ggx {
+        Ggx::from_roughness(roughness_at(self.roughness.as_ref(), hr))
+    }
+
+    // absorbed along `r` when it comes from inside
+    fn transmittance(&self, r: &Ray<f32>, hr: &HitRecord<f32>) -> Vec3<f32> {
+        if Vec3::dot(&r.direction, &hr.normal) > 0.0 {
+            transmittance(r, hr, &self.absorption)
+        } else {
+            Vec3::new(Some([1.0, 1.0, 1.0]))
+        }
+    }
+}
+
+impl Material<f32> for RoughDielectric<f32> {
+    fn scatter(&self, r: &Ray<f32>, hr: &HitRecord<f32>) -> Option<ScatterResult<f32>> {
+        let ggx = self.ggx(hr);
+        if ggx.is_smooth() {
+            let dielectric = Dielectric { ref_idx: self.ref_idx, absorption: self.absorption, dispersion: self.dispersion };
+            return dielectric.scatter(r, hr);
+        }
+        let (ref_idx, weight, wavelengths) = refractive_index(self.ref_idx, &self.dispersion, r);
+        let (uvw, wo, back) = viewer_frame(r, hr);
+        // index of the far side over the index of the side of the viewer
+        let eta = if back { 1.0 / ref_idx } else { ref_idx };
+        if wo[2] <= 0.0 {
+            return None;
+        }
+
+        let pdf = GgxDielectricPdf { uvw, wo, eta, ggx };
+        let (wi, reflected) = pdf.sample();
+        // reflections below the surface and refractions above it are
+        // absorbed, the lights are still sampled. Otherwise D, F and the
+        // change of variables cancel out of eval / pdf. Like `Dielectric`,
+        // radiance is not scaled by the squared ratio of the indices, which
+        // cancels out for light that leaves the medium again.
+        let attenuation = if (wi[2] > 0.0) == reflected && wi[2] != 0.0 {
+            weight * self.transmittance(r, hr) * (ggx.g(&wo, &wi) / ggx.g1(&wo))
+        } else {
+            Vec3::new(None)
+        };
+
+        Some(ScatterResult {
+            attenuation,
+            scattered: Ray {
+                origin: hr.p,
+                direction: pdf.uvw.local(&wi),
+                time: r.time,
+                wavelengths,
+            },
+            pdf: Some(Box::new(pdf)),
+        })
+    }
+
+    fn eval(&self, r: &Ray<f32>, hr: &HitRecord<f32>, scattered: &Ray<f32>) -> Vec3<f32> {
+        let zero = Vec3::new(None);
+        let ggx = self.ggx(hr);
+        if ggx.is_smooth() {
+            return zero;
+        }
+        let (ref_idx, weight, _) = refractive_index(self.ref_idx, &self.dispersion, r);
+        let (uvw, wo, back) = viewer_frame(r, hr);
+        let eta = if back { 1.0 / ref_idx } else { ref_idx };
+        let wi = Vec3::unit_vector(uvw.components(&scattered.direction));
+        weight * self.transmittance(r, hr) * dielectric_bsdf(&ggx, &wo, &wi, eta)
+    }
+}
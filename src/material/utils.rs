@@ -1,4 +1,9 @@
 use crate::vec3::Vec3;
+use crate::ray::Ray;
+use crate::hitable::HitRecord;
+use crate::spectrum::{self, Wavelengths};
+
+use super::Dispersion;
 
 pub fn reflect(v: Vec3<f32>, n: Vec3<f32>) -> Vec3<f32> {
     v - n * Vec3::dot(&v, &n) * 2.0
@@ -20,3 +25,114 @@ pub fn schlick(cosine: f32, ref_idx: f32) -> f32 {
     let r0 = r0*r0;
     r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
 }
+
+/// Reflectance of a conductor with complex refractive index eta + ik, for
+/// unpolarized light at incidence `cosine`, per channel
+pub fn fresnel_conductor(cosine: f32, eta: &Vec3<f32>, k: &Vec3<f32>) -> Vec3<f32> {
+    let cos2 = cosine.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+    let mut r = [0.0; 3];
+    for (i, r) in r.iter_mut().enumerate() {
+        let (eta2, k2) = (eta[i] * eta[i], k[i] * k[i]);
+        let t0 = eta2 - k2 - sin2;
+        let a2b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+        let a = (0.5 * (a2b2 + t0)).max(0.0).sqrt();
+        let t1 = a2b2 + cos2;
+        let t2 = 2.0 * cosine.max(0.0) * a;
+        let rs = (t1 - t2) / (t1 + t2);
+        let t3 = cos2 * a2b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+        *r = 0.5 * (rs + rp);
+    }
+    Vec3::new(Some(r))
+}
+
+/// Reflectance of the boundary with a dielectric of relative index `eta`,
+/// the index on the far side over the index on the side of incidence
+/// `cosine`, for unpolarized light. One on total internal reflection.
+pub fn fresnel_dielectric(cosine: f32, eta: f32) -> f32 {
+    let cos_i = cosine.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (r_parallel * r_parallel + r_perpendicular * r_perpendicular)
+}
+
+/// Fraction of the light left by Beer-Lambert absorption along `r`, from
+/// its origin to the hit, for rays inside a medium
+pub fn transmittance(r: &Ray<f32>, hr: &HitRecord<f32>, absorption: &Vec3<f32>) -> Vec3<f32> {
+    let distance = hr.t * r.direction.length();
+    let absorption = spectrum::reflectance(&r.wavelengths, *absorption);
+    let mut e = [0.0; 3];
+    for (i, e) in e.iter_mut().enumerate() {
+        *e = (-absorption[i] * distance).exp();
+    }
+    Vec3::new(Some(e))
+}
+
+/// Refractive index seen by `r`. With dispersion in spectral mode only the
+/// hero wavelength is followed, this also returns the weight of the
+/// scattered light and the wavelengths for the scattered ray.
+pub fn refractive_index(ref_idx: f32, dispersion: &Option<Dispersion>, r: &Ray<f32>) -> (f32, Vec3<f32>, Option<Wavelengths>) {
+    match (dispersion, &r.wavelengths) {
+        (Some(dispersion), Some(w)) => {
+            let (weight, wavelengths) = w.terminate_secondary();
+            (dispersion.ior(w.hero()), weight, Some(wavelengths))
+        },
+        _ => (ref_idx, Vec3::new(Some([1.0, 1.0, 1.0])), r.wavelengths),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conductor_reflectance_at_normal_and_grazing_incidence() {
+        let eta = Vec3::new(Some([0.2, 1.0, 2.5]));
+        let k = Vec3::new(Some([3.9, 0.5, 1.2]));
+        let r = fresnel_conductor(1.0, &eta, &k);
+        for i in 0..3 {
+            let expected = ((eta[i] - 1.0).powi(2) + k[i] * k[i]) / ((eta[i] + 1.0).powi(2) + k[i] * k[i]);
+            assert!((r[i] - expected).abs() < 1e-5, "{} != {}", r[i], expected);
+        }
+
+        let grazing = fresnel_conductor(0.0, &eta, &k);
+        assert!(grazing.e.iter().all(|&x| (x - 1.0).abs() < 1e-5), "{:?}", grazing.e);
+    }
+
+    #[test]
+    fn dielectric_reflectance_at_normal_incidence() {
+        for &eta in &[1.5f32, 2.4, 1.0 / 1.5] {
+            let expected = ((eta - 1.0) / (eta + 1.0)).powi(2);
+            assert!((fresnel_dielectric(1.0, eta) - expected).abs() < 1e-6);
+        }
+        assert_eq!(fresnel_dielectric(0.7, 1.0), 0.0);
+    }
+
+    #[test]
+    fn dielectric_total_internal_reflection() {
+        let eta = 1.0f32 / 1.5;
+        let critical = (1.0 - eta * eta).sqrt();
+        assert_eq!(fresnel_dielectric(critical - 1e-3, eta), 1.0);
+        assert_eq!(fresnel_dielectric(0.0, eta), 1.0);
+        assert!(fresnel_dielectric(critical + 1e-2, eta) < 1.0);
+    }
+
+    #[test]
+    fn dielectric_reflectance_is_symmetric() {
+        // the same for light crossing the boundary the other way
+        let (eta, cos_i) = (1.5f32, 0.4f32);
+        let cos_t = (1.0 - (1.0 - cos_i * cos_i) / (eta * eta)).sqrt();
+        assert!((fresnel_dielectric(cos_i, eta) - fresnel_dielectric(cos_t, 1.0 / eta)).abs() < 1e-6);
+
+        // and a conductor without absorption
+        let conductor = fresnel_conductor(cos_i, &Vec3::new(Some([eta; 3])), &Vec3::new(None));
+        assert!((fresnel_dielectric(cos_i, eta) - conductor[0]).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,75 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+use crate::ray::Ray;
+use crate::aabb::Aabb;
+use crate::hitable::{Hitable, HitResult};
+use crate::material::Material;
+use crate::sphere::{hit_sphere, sphere_bounding_box};
+
+/// Sphere moving linearly from `center0` at `time0` to `center1` at `time1`,
+/// resting at those centres before and after
+pub struct MovingSphere<T: Float+MulAssign> {
+    pub center0: Vec3<T>,
+    pub center1: Vec3<T>,
+    pub time0: T,
+    pub time1: T,
+    pub radius: T,
+    pub material: Box<dyn Material<T>>,
+}
+
+impl<T: Float+MulAssign> MovingSphere<T> {
+    pub fn center(&self, time: T) -> Vec3<T> {
+        if self.time1 == self.time0 {
+            return self.center0;
+        }
+        let s = ((time - self.time0) / (self.time1 - self.time0)).max(T::zero()).min(T::one());
+        self.center0 + (self.center1 - self.center0) * s
+    }
+}
+
+impl<T: Float+MulAssign+Send+Sync> Hitable<T> for MovingSphere<T> {
+    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'_, T>> {
+        hit_sphere(self.center(r.time), self.radius, self.material.as_ref(), r, t_min, t_max)
+    }
+
+    // covers the sphere at all times, since it stops at its ends
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        Some(Aabb::surrounding_box(
+            &sphere_bounding_box(self.center0, self.radius),
+            &sphere_bounding_box(self.center1, self.radius),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::texture::ConstantTexture;
+
+    #[test]
+    fn sphere_rests_outside_its_interval() {
+        let sphere = MovingSphere {
+            center0: Vec3::new(Some([0.0, 0.0, 0.0])),
+            center1: Vec3::new(Some([2.0, 0.0, 0.0])),
+            time0: 0.0,
+            time1: 1.0,
+            radius: 0.5,
+            material: Box::new(Lambertian {
+                albedo: Box::new(ConstantTexture { color: Vec3::new(None) }),
+            }),
+        };
+        assert_eq!(sphere.center(0.5).e, [1.0, 0.0, 0.0]);
+        assert_eq!(sphere.center(-1.0).e, [0.0, 0.0, 0.0]);
+        assert_eq!(sphere.center(3.0).e, [2.0, 0.0, 0.0]);
+
+        // and stays inside its bounding box
+        let bbox = sphere.bounding_box().unwrap();
+        for &time in &[-1.0, 0.25, 3.0] {
+            let c = sphere.center(time);
+            assert!((0..3).all(|a| bbox.min[a] <= c[a] - 0.5 && c[a] + 0.5 <= bbox.max[a]));
+        }
+    }
+}
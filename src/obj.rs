@@ -0,0 +1,456 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::vec3::Vec3;
+use crate::trianglemesh::TriangleMesh;
+use crate::material::{Material, Lambertian, Metal, Dielectric};
+use crate::texture::ConstantTexture;
+
+#[derive(Debug)]
+pub enum ObjError {
+    Io(PathBuf, io::Error),
+    // a mesh that cannot be built from otherwise valid lines
+    Mesh(PathBuf, String),
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObjError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            ObjError::Mesh(path, e) => write!(f, "{}: {}", path.display(), e),
+            ObjError::Parse { path, line, message } => write!(f, "{}:{}: {}", path.display(), line, message),
+        }
+    }
+}
+
+impl Error for ObjError {}
+
+/// Material parameters read from an MTL file
+#[derive(Clone)]
+struct MtlMaterial {
+    kd: [f32; 3],
+    ks: [f32; 3],
+    ns: f32,
+    ni: f32,
+    d: f32,
+    illum: u32,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> MtlMaterial {
+        MtlMaterial {
+            kd: [0.8, 0.8, 0.8],
+            ks: [0.0, 0.0, 0.0],
+            ns: 0.0,
+            ni: 1.0,
+            d: 1.0,
+            illum: 2,
+        }
+    }
+}
+
+impl MtlMaterial {
+    // transparent materials become Dielectric, mirror-like ones (illum 3 or
+    // only a specular colour) become Metal with the fuzz derived from the
+    // specular exponent, everything else is Lambertian
+    fn to_material(&self) -> Box<dyn Material<f32>> {
+        let is_black = |c: &[f32; 3]| c.iter().all(|&x| x <= 0.0);
+
+        if self.d < 1.0 || [4, 6, 7, 9].contains(&self.illum) {
+            Box::new(Dielectric {
+                ref_idx: if self.ni > 1.0 { self.ni } else { 1.5 },
+                absorption: Vec3::new(None),
+                dispersion: None,
+            })
+        } else if !is_black(&self.ks) && (self.illum == 3 || is_black(&self.kd)) {
+            Box::new(Metal {
+                albedo: Box::new(ConstantTexture { color: Vec3::new(Some(self.ks)) }),
+                fuzz: (1.0 - (self.ns / 1000.0).max(0.0).sqrt()).clamp(0.0, 1.0),
+            })
+        } else {
+            Box::new(Lambertian {
+                albedo: Box::new(ConstantTexture { color: Vec3::new(Some(self.kd)) }),
+            })
+        }
+    }
+}
+
+fn parse_error<E>(path: &Path, line: usize, message: String) -> Result<E, ObjError> {
+    Err(ObjError::Parse {
+        path: path.to_path_buf(),
+        line,
+        message,
+    })
+}
+
+fn parse_floats<'a, I: Iterator<Item=&'a str>>(args: I, n: usize, path: &Path, line: usize) -> Result<Vec<f32>, ObjError> {
+    let mut result = Vec::with_capacity(n);
+    for a in args.take(n) {
+        match a.parse::<f32>() {
+            Ok(x) => result.push(x),
+            Err(_) => return parse_error(path, line, format!("invalid number '{}'", a)),
+        }
+    }
+    if result.len() < n {
+        return parse_error(path, line, format!("expected {} numbers, found {}", n, result.len()));
+    }
+    Ok(result)
+}
+
+fn read_file(path: &Path) -> Result<String, ObjError> {
+    fs::read_to_string(path).map_err(|e| ObjError::Io(path.to_path_buf(), e))
+}
+
+fn load_mtl(path: &Path, materials: &mut HashMap<String, MtlMaterial>) -> Result<(), ObjError> {
+    let contents = read_file(path)?;
+    let mut current: Option<String> = None;
+
+    for (n, l) in contents.lines().enumerate() {
+        let line = n + 1;
+        let mut tokens = l.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(k) if !k.starts_with('#') => k,
+            _ => continue,
+        };
+
+        if keyword == "newmtl" {
+            let name = tokens.collect::<Vec<_>>().join(" ");
+            if name.is_empty() {
+                return parse_error(path, line, "newmtl without a name".to_string());
+            }
+            materials.insert(name.clone(), MtlMaterial::default());
+            current = Some(name);
+            continue;
+        }
+
+        let m = match &current {
+            Some(name) => materials.get_mut(name).unwrap(),
+            None => return parse_error(path, line, format!("'{}' before any newmtl", keyword)),
+        };
+        match keyword {
+            "Kd" => {
+                let c = parse_floats(tokens, 3, path, line)?;
+                m.kd = [c[0], c[1], c[2]];
+            },
+            "Ks" => {
+                let c = parse_floats(tokens, 3, path, line)?;
+                m.ks = [c[0], c[1], c[2]];
+            },
+            "Ns" => m.ns = parse_floats(tokens, 1, path, line)?[0],
+            "Ni" => m.ni = parse_floats(tokens, 1, path, line)?[0],
+            "d" => m.d = parse_floats(tokens, 1, path, line)?[0],
+            "Tr" => m.d = 1.0 - parse_floats(tokens, 1, path, line)?[0],
+            "illum" => {
+                m.illum = match tokens.next().map(|t| t.parse::<u32>()) {
+                    Some(Ok(i)) => i,
+                    _ => return parse_error(path, line, "invalid illum".to_string()),
+                }
+            },
+            // ambient, emission, texture maps etc. are not supported
+            _ => {},
+        }
+    }
+
+    Ok(())
+}
+
+// resolves a 1-based, possibly negative, OBJ index
+fn resolve_index(token: &str, len: usize, path: &Path, line: usize) -> Result<usize, ObjError> {
+    let i = match token.parse::<i64>() {
+        Ok(i) => i,
+        Err(_) => return parse_error(path, line, format!("invalid index '{}'", token)),
+    };
+    let resolved = if i < 0 { len as i64 + i } else { i - 1 };
+    if resolved < 0 || resolved >= len as i64 {
+        return parse_error(path, line, format!("index {} out of range", i));
+    }
+    Ok(resolved as usize)
+}
+
+type VertexKey = (usize, Option<usize>, Option<usize>);
+
+/// Triangles sharing the same group and material
+struct MeshBuilder {
+    material: Option<String>,
+    vertices: Vec<VertexKey>,
+    lookup: HashMap<VertexKey, usize>,
+    indices: Vec<[usize; 3]>,
+}
+
+impl MeshBuilder {
+    fn new(material: Option<String>) -> MeshBuilder {
+        MeshBuilder {
+            material,
+            vertices: Vec::new(),
+            lookup: HashMap::new(),
+            indices: Vec::new(),
+        }
+    }
+
+    fn vertex(&mut self, key: VertexKey) -> usize {
+        let vertices = &mut self.vertices;
+        *self.lookup.entry(key).or_insert_with(|| {
+            vertices.push(key);
+            vertices.len() - 1
+        })
+    }
+}
+
+/// Loads the Wavefront OBJ file at `path`, together with the MTL libraries
+/// it references, returning one mesh per group and material. Polygons are
+/// triangulated as fans.
+pub fn load_obj(path: &Path) -> Result<Vec<TriangleMesh<f32>>, ObjError> {
+    let contents = read_file(path)?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut positions: Vec<Vec3<f32>> = Vec::new();
+    let mut normals: Vec<Vec3<f32>> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut materials: HashMap<String, MtlMaterial> = HashMap::new();
+
+    let mut group = String::new();
+    let mut material: Option<String> = None;
+    let mut builders: HashMap<(String, Option<String>), MeshBuilder> = HashMap::new();
+    // keeps the output in file order
+    let mut order: Vec<(String, Option<String>)> = Vec::new();
+
+    for (n, l) in contents.lines().enumerate() {
+        let line = n + 1;
+        let mut tokens = l.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(k) if !k.starts_with('#') => k,
+            _ => continue,
+        };
+
+        match keyword {
+            "v" => {
+                let p = parse_floats(tokens, 3, path, line)?;
+                positions.push(Vec3::new(Some([p[0], p[1], p[2]])));
+            },
+            "vn" => {
+                let p = parse_floats(tokens, 3, path, line)?;
+                normals.push(Vec3::new(Some([p[0], p[1], p[2]])));
+            },
+            "vt" => {
+                // v is optional, as is w which is ignored
+                let u = parse_floats(tokens.next().into_iter(), 1, path, line)?[0];
+                let v = match tokens.next() {
+                    Some(t) => parse_floats(std::iter::once(t), 1, path, line)?[0],
+                    None => 0.0,
+                };
+                uvs.push([u, v]);
+            },
+            "f" => {
+                let mut face: Vec<VertexKey> = Vec::new();
+                for t in tokens {
+                    let mut parts = t.split('/');
+                    let v = resolve_index(parts.next().unwrap(), positions.len(), path, line)?;
+                    let vt = match parts.next() {
+                        Some(s) if !s.is_empty() => Some(resolve_index(s, uvs.len(), path, line)?),
+                        _ => None,
+                    };
+                    let vn = match parts.next() {
+                        Some(s) if !s.is_empty() => Some(resolve_index(s, normals.len(), path, line)?),
+                        _ => None,
+                    };
+                    face.push((v, vt, vn));
+                }
+                if face.len() < 3 {
+                    return parse_error(path, line, format!("face with {} vertices", face.len()));
+                }
+
+                let key = (group.clone(), material.clone());
+                if !builders.contains_key(&key) {
+                    order.push(key.clone());
+                }
+                let builder = builders.entry(key).or_insert_with(|| MeshBuilder::new(material.clone()));
+                let first = builder.vertex(face[0]);
+                for w in face[1..].windows(2) {
+                    let b = builder.vertex(w[0]);
+                    let c = builder.vertex(w[1]);
+                    builder.indices.push([first, b, c]);
+                }
+            },
+            "g" | "o" => {
+                group = tokens.collect::<Vec<_>>().join(" ");
+            },
+            "usemtl" => {
+                let name = tokens.collect::<Vec<_>>().join(" ");
+                if !materials.contains_key(&name) {
+                    return parse_error(path, line, format!("unknown material '{}'", name));
+                }
+                material = Some(name);
+            },
+            "mtllib" => {
+                for lib in tokens {
+                    load_mtl(&dir.join(lib), &mut materials).map_err(|e| match e {
+                        ObjError::Io(p, e) => ObjError::Parse {
+                            path: path.to_path_buf(),
+                            line,
+                            message: format!("{}: {}", p.display(), e),
+                        },
+                        e => e,
+                    })?;
+                }
+            },
+            // smoothing groups, lines, points etc. are ignored
+            _ => {},
+        }
+    }
+
+    let default_material = MtlMaterial::default();
+    let mut result = Vec::with_capacity(order.len());
+    for key in order {
+        let builder = builders.remove(&key).unwrap();
+
+        // normals and uvs are used only if every vertex of the mesh has them
+        let mesh_positions = builder.vertices.iter().map(|&(v, _, _)| positions[v]).collect();
+        let mesh_uvs = builder.vertices.iter().map(|&(_, vt, _)| vt.map(|i| uvs[i])).collect();
+        let mesh_normals = builder.vertices.iter().map(|&(_, _, vn)| vn.map(|i| normals[i])).collect();
+        let mtl = match &builder.material {
+            Some(name) => &materials[name],
+            None => &default_material,
+        };
+
+        let mesh = TriangleMesh::new(
+            mesh_positions,
+            mesh_normals,
+            mesh_uvs,
+            builder.indices,
+            mtl.to_material(),
+        ).map_err(|e| ObjError::Mesh(path.to_path_buf(), e))?;
+        result.push(mesh);
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hitable::Hitable;
+    use crate::ray::Ray;
+
+    // writes `contents` to a file of its own in the temporary directory
+    fn write_obj(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rtinaweekend-{}-{}.obj", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn load(name: &str, contents: &str) -> Result<Vec<TriangleMesh<f32>>, ObjError> {
+        let path = write_obj(name, contents);
+        let result = load_obj(&path);
+        fs::remove_file(&path).unwrap();
+        result
+    }
+
+    // whether a ray straight down onto the z = 0 plane hits the mesh at (x, y)
+    fn hits(mesh: &TriangleMesh<f32>, x: f32, y: f32) -> bool {
+        let r = Ray {
+            origin: Vec3::new(Some([x, y, 1.0])),
+            direction: Vec3::new(Some([0.0, 0.0, -1.0])),
+            time: 0.0,
+            wavelengths: None,
+        };
+        mesh.hit(&r, 0.0, 10.0).is_some()
+    }
+
+    #[test]
+    fn polygons_are_triangulated_as_fans() {
+        let meshes = load("fan", "\
+v 0 0 0
+v 2 0 0
+v 3 1 0
+v 2 2 0
+v 0 2 0
+f 1 2 3 4 5
+").unwrap();
+        assert_eq!(meshes.len(), 1);
+        // one point inside each of the three triangles of the fan
+        for &(x, y) in &[(1.0, 0.2), (2.5, 1.0), (1.0, 1.8)] {
+            assert!(hits(&meshes[0], x, y), "({}, {}) missed", x, y);
+        }
+        assert!(!hits(&meshes[0], 2.9, 1.8));
+    }
+
+    #[test]
+    fn negative_indices_count_from_the_end() {
+        let meshes = load("negative", "\
+v 5 5 0
+v 0 0 0
+v 1 0 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 0 1
+f -3/-3 -2/-2 -1/-1
+").unwrap();
+        assert!(hits(&meshes[0], 0.2, 0.2));
+        assert!(!hits(&meshes[0], 4.0, 4.0));
+    }
+
+    #[test]
+    fn groups_split_meshes() {
+        let meshes = load("groups", "\
+v 0 0 0
+v 1 0 0
+v 0 1 0
+g first
+f 1 2 3
+g second
+f 1 2 3
+g first
+f 3 2 1
+").unwrap();
+        assert_eq!(meshes.len(), 2);
+    }
+
+    fn parse_message(name: &str, contents: &str) -> (usize, String) {
+        match load(name, contents) {
+            Err(ObjError::Parse { line, message, .. }) => (line, message),
+            Err(e) => panic!("unexpected error {}", e),
+            Ok(_) => panic!("loaded invalid OBJ"),
+        }
+    }
+
+    #[test]
+    fn bad_indices_are_errors() {
+        let vertices = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+        assert_eq!(parse_message("zero", &format!("{}f 0 1 2\n", vertices)), (4, "index 0 out of range".to_string()));
+        assert_eq!(parse_message("past", &format!("{}f 1 2 4\n", vertices)), (4, "index 4 out of range".to_string()));
+        assert_eq!(parse_message("before", &format!("{}f -4 1 2\n", vertices)), (4, "index -4 out of range".to_string()));
+        assert_eq!(parse_message("text", &format!("{}f 1 2 x\n", vertices)), (4, "invalid index 'x'".to_string()));
+        assert_eq!(parse_message("uv", &format!("{}f 1/1 2/1 3/1\n", vertices)), (4, "index 1 out of range".to_string()));
+        assert_eq!(parse_message("short", &format!("{}f 1 2\n", vertices)), (4, "face with 2 vertices".to_string()));
+    }
+
+    #[test]
+    fn unknown_materials_are_errors() {
+        assert_eq!(parse_message("usemtl", "usemtl missing\n"), (1, "unknown material 'missing'".to_string()));
+    }
+
+    #[test]
+    fn texture_coordinates_default_v_to_zero() {
+        let meshes = load("vt", "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.5\nvt 0.25 1 0\nf 1/1 2/2 3/1\n").unwrap();
+        let r = Ray {
+            origin: Vec3::new(Some([0.0, 0.5, 1.0])),
+            direction: Vec3::new(Some([0.0, 0.0, -1.0])),
+            time: 0.0,
+            wavelengths: None,
+        };
+        let rec = meshes[0].hit(&r, 0.0, 10.0).unwrap().rec;
+        assert_eq!((rec.u, rec.v), (0.5, 0.0));
+        assert_eq!(parse_message("vt_empty", "vt\n"), (1, "expected 1 numbers, found 0".to_string()));
+        assert_eq!(parse_message("vt_text", "vt 0.5 x\n"), (1, "invalid number 'x'".to_string()));
+    }
+}
@@ -0,0 +1,35 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+
+/// Orthonormal basis, used to place directions sampled around the z axis
+/// around an arbitrary direction `w`
+pub struct Onb<T: Float+MulAssign> {
+    pub u: Vec3<T>,
+    pub v: Vec3<T>,
+    pub w: Vec3<T>,
+}
+
+impl<T: Float+MulAssign> Onb<T> {
+    pub fn from_w(n: &Vec3<T>) -> Onb<T> {
+        let w = Vec3::unit_vector(*n);
+        let a = if w[0].abs() > T::from(0.9).unwrap() {
+            Vec3::new(Some([T::zero(), T::one(), T::zero()]))
+        } else {
+            Vec3::new(Some([T::one(), T::zero(), T::zero()]))
+        };
+        let v = Vec3::unit_vector(Vec3::cross(&w, &a));
+        let u = Vec3::cross(&w, &v);
+        Onb { u, v, w }
+    }
+
+    pub fn local(&self, a: &Vec3<T>) -> Vec3<T> {
+        self.u * a[0] + self.v * a[1] + self.w * a[2]
+    }
+
+    /// Coordinates of `a` in the basis, the inverse of `local`
+    pub fn components(&self, a: &Vec3<T>) -> Vec3<T> {
+        Vec3::new(Some([Vec3::dot(a, &self.u), Vec3::dot(a, &self.v), Vec3::dot(a, &self.w)]))
+    }
+}
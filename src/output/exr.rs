@@ -0,0 +1,136 @@
+use std::io::{self, Write};
+
+use half::f16;
+
+use crate::framebuffer::Framebuffer;
+
+use super::ImageWriter;
+
+/// Single part, scanline, uncompressed OpenEXR with R, G and B channels
+/// stored as half or full floats.
+pub struct ExrWriter {
+    pub half: bool,
+}
+
+fn attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    header.extend_from_slice(name.as_bytes());
+    header.push(0);
+    header.extend_from_slice(kind.as_bytes());
+    header.push(0);
+    header.extend_from_slice(&(value.len() as i32).to_le_bytes());
+    header.extend_from_slice(value);
+}
+
+impl ImageWriter for ExrWriter {
+    fn write(&self, fb: &Framebuffer, out: &mut dyn Write) -> io::Result<()> {
+        let (pixel_type, bytes_per_value): (i32, usize) = if self.half { (1, 2) } else { (2, 4) };
+        let width = fb.width();
+        let height = fb.height();
+
+        // magic number and version 2, single part scanline file
+        let mut header: Vec<u8> = vec![0x76, 0x2f, 0x31, 0x01, 2, 0, 0, 0];
+
+        // channels have to be sorted by name
+        let mut channels = Vec::new();
+        for name in &["B", "G", "R"] {
+            channels.extend_from_slice(name.as_bytes());
+            channels.push(0);
+            channels.extend_from_slice(&pixel_type.to_le_bytes());
+            // pLinear and reserved
+            channels.extend_from_slice(&[0, 0, 0, 0]);
+            // x and y sampling
+            channels.extend_from_slice(&1i32.to_le_bytes());
+            channels.extend_from_slice(&1i32.to_le_bytes());
+        }
+        channels.push(0);
+        attribute(&mut header, "channels", "chlist", &channels);
+        attribute(&mut header, "compression", "compression", &[0]);
+
+        let mut window = Vec::new();
+        for v in &[0, 0, width as i32 - 1, height as i32 - 1] {
+            window.extend_from_slice(&v.to_le_bytes());
+        }
+        attribute(&mut header, "dataWindow", "box2i", &window);
+        attribute(&mut header, "displayWindow", "box2i", &window);
+        // increasing y
+        attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+        attribute(&mut header, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+        attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+        attribute(&mut header, "screenWindowWidth", "float", &1f32.to_le_bytes());
+        header.push(0);
+        out.write_all(&header)?;
+
+        // offset table, one scanline per chunk
+        let line_size = width * 3 * bytes_per_value;
+        let chunk_size = 8 + line_size;
+        let first_chunk = header.len() + height * 8;
+        for y in 0..height {
+            out.write_all(&((first_chunk + y * chunk_size) as u64).to_le_bytes())?;
+        }
+
+        let mut chunk = Vec::with_capacity(chunk_size);
+        for y in 0..height {
+            chunk.clear();
+            chunk.extend_from_slice(&(y as i32).to_le_bytes());
+            chunk.extend_from_slice(&(line_size as i32).to_le_bytes());
+            for c in (0..3).rev() {
+                for x in 0..width {
+                    let v = fb.pixel(x, y)[c];
+                    if self.half {
+                        chunk.extend_from_slice(&f16::from_f32(v).to_le_bytes());
+                    } else {
+                        chunk.extend_from_slice(&v.to_le_bytes());
+                    }
+                }
+            }
+            out.write_all(&chunk)?;
+        }
+
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Vec3;
+
+    fn test_image(width: usize, height: usize) -> Framebuffer {
+        let mut fb = Framebuffer::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let f = ((x * 7919 + y * 104_729) % 1000) as f32 / 100.0;
+                fb.accumulate(x, y, Vec3::new(Some([f, 0.5 * x as f32, 1000.0 / (1.0 + y as f32)])), 1);
+            }
+        }
+        fb
+    }
+
+    fn round_trip(half: bool) {
+        let (width, height) = (13, 7);
+        let fb = test_image(width, height);
+        let mut data = Vec::new();
+        ExrWriter { half }.write(&fb, &mut data).unwrap();
+
+        let img = image::load_from_memory(&data).unwrap().to_rgb32f();
+        assert_eq!((img.width() as usize, img.height() as usize), (width, height));
+        for (x, y, p) in img.enumerate_pixels() {
+            let expected = fb.pixel(x as usize, y as usize);
+            for c in 0..3 {
+                // half floats keep 11 bits of mantissa
+                let tolerance = if half { expected[c].abs() / 1024.0 } else { 0.0 };
+                assert!((p[c] - expected[c]).abs() <= tolerance, "pixel ({}, {}): {:?} != {:?}", x, y, p.0, expected.e);
+            }
+        }
+    }
+
+    #[test]
+    fn float_channels_round_trip() {
+        round_trip(false);
+    }
+
+    #[test]
+    fn half_channels_round_trip() {
+        round_trip(true);
+    }
+}
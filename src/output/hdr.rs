@@ -0,0 +1,171 @@
+use std::io::{self, Write};
+
+use crate::framebuffer::Framebuffer;
+
+use super::ImageWriter;
+
+/// Radiance RGBE (.hdr), with run length encoded scanlines
+pub struct HdrWriter {}
+
+fn to_rgbe(r: f32, g: f32, b: f32) -> [u8; 4] {
+    let v = r.max(g).max(b);
+    if v < 1e-32 {
+        return [0, 0, 0, 0];
+    }
+
+    // v = m * 2^e with m in [0.5, 1), as frexp would give
+    let mut e = v.log2().floor() as i32 + 1;
+    if v / 2f32.powi(e) >= 1.0 {
+        e += 1;
+    }
+    let scale = 256.0 / 2f32.powi(e);
+    [
+        (r.max(0.0) * scale) as u8,
+        (g.max(0.0) * scale) as u8,
+        (b.max(0.0) * scale) as u8,
+        (e + 128) as u8,
+    ]
+}
+
+// scanlines of these widths are run length encoded, readers take others
+// as flat
+const MIN_ENCODED_WIDTH: usize = 8;
+const MAX_ENCODED_WIDTH: usize = 0x7fff;
+// shorter runs are cheaper as literal bytes
+const MIN_RUN: usize = 4;
+
+// one component of a scanline, as runs of a repeated byte of at most 127
+// and dumps of literal bytes of at most 128
+fn encode_component(data: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < data.len() {
+        let (mut run_start, mut run_len) = (i, 0);
+        while run_start < data.len() {
+            run_len = data[run_start..].iter().take(127).take_while(|&&b| b == data[run_start]).count();
+            if run_len >= MIN_RUN {
+                break;
+            }
+            run_start += run_len;
+        }
+
+        while i < run_start {
+            let n = (run_start - i).min(128);
+            out.push(n as u8);
+            out.extend_from_slice(&data[i..i + n]);
+            i += n;
+        }
+        if run_start < data.len() {
+            out.push(128 + run_len as u8);
+            out.push(data[run_start]);
+            i = run_start + run_len;
+        }
+    }
+}
+
+// a scanline of RGBE pixels, run length encoded when its width allows it.
+// Flat scanlines cannot be mistaken for encoded ones since readers only
+// look for the marker of encoded scanlines at the widths they can have.
+fn encode_scanline(pixels: &[[u8; 4]], out: &mut Vec<u8>) {
+    let width = pixels.len();
+    if !(MIN_ENCODED_WIDTH..=MAX_ENCODED_WIDTH).contains(&width) {
+        for p in pixels {
+            out.extend_from_slice(p);
+        }
+        return;
+    }
+
+    out.extend_from_slice(&[2, 2, (width >> 8) as u8, (width & 0xff) as u8]);
+    let mut component = Vec::with_capacity(width);
+    for c in 0..4 {
+        component.clear();
+        component.extend(pixels.iter().map(|p| p[c]));
+        encode_component(&component, out);
+    }
+}
+
+impl ImageWriter for HdrWriter {
+    fn write(&self, fb: &Framebuffer, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", fb.height(), fb.width())?;
+
+        let mut pixels = Vec::with_capacity(fb.width());
+        let mut line = Vec::with_capacity(fb.width() * 4);
+        for y in 0..fb.height() {
+            pixels.clear();
+            for x in 0..fb.width() {
+                let col = fb.pixel(x, y);
+                pixels.push(to_rgbe(col[0], col[1], col[2]));
+            }
+            line.clear();
+            encode_scanline(&pixels, &mut line);
+            out.write_all(&line)?;
+        }
+
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Vec3;
+
+    // an image with long runs of one colour and noisy stretches
+    fn test_image(width: usize, height: usize) -> Framebuffer {
+        let mut fb = Framebuffer::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let c = if (x / 40 + y) % 2 == 0 {
+                    [0.25, 0.5, 1.0]
+                } else {
+                    let f = ((x * 7919 + y * 104_729) % 1000) as f32 / 100.0;
+                    [f, 0.1 * f, 3.0]
+                };
+                fb.accumulate(x, y, Vec3::new(Some(c)), 1);
+            }
+        }
+        fb
+    }
+
+    fn round_trip(width: usize, height: usize) {
+        let fb = test_image(width, height);
+        let mut data = Vec::new();
+        HdrWriter {}.write(&fb, &mut data).unwrap();
+
+        let img = image::load_from_memory_with_format(&data, image::ImageFormat::Hdr).unwrap().to_rgb32f();
+        assert_eq!((img.width() as usize, img.height() as usize), (width, height));
+        for (x, y, p) in img.enumerate_pixels() {
+            let expected = fb.pixel(x as usize, y as usize);
+            // 8 bits of mantissa shared by the channels
+            let tolerance = expected.e.iter().cloned().fold(0.0, f32::max) / 64.0;
+            for c in 0..3 {
+                assert!((p[c] - expected[c]).abs() <= tolerance, "pixel ({}, {}): {:?} != {:?}", x, y, p.0, expected.e);
+            }
+        }
+    }
+
+    #[test]
+    fn encoded_scanlines_round_trip() {
+        round_trip(300, 4);
+    }
+
+    #[test]
+    fn narrow_scanlines_are_flat() {
+        round_trip(5, 3);
+        let mut line = Vec::new();
+        encode_scanline(&[[1, 2, 3, 4]; 5], &mut line);
+        assert_eq!(line.len(), 5 * 4);
+    }
+
+    #[test]
+    fn runs_and_dumps_are_split_at_their_limits() {
+        let mut out = Vec::new();
+        encode_component(&[9; 300], &mut out);
+        assert_eq!(out, vec![255, 9, 255, 9, 128 + 46, 9]);
+
+        let data: Vec<u8> = (0..200).map(|i| i as u8).collect();
+        out.clear();
+        encode_component(&data, &mut out);
+        assert_eq!(out.len(), 2 + 200);
+        assert_eq!((out[0], out[129]), (128, 72));
+    }
+}
@@ -0,0 +1,58 @@
+mod ppm;
+mod png;
+mod hdr;
+mod pfm;
+mod exr;
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::framebuffer::Framebuffer;
+
+pub use self::ppm::PpmWriter;
+pub use self::png::PngWriter;
+pub use self::hdr::HdrWriter;
+pub use self::pfm::PfmWriter;
+pub use self::exr::ExrWriter;
+
+pub trait ImageWriter {
+    fn write(&self, fb: &Framebuffer, out: &mut dyn Write) -> io::Result<()>;
+}
+
+/// Gamma 2 correction followed by quantization to 8 bits, the encoding
+/// stage of the low dynamic range writers
+pub fn encode_rgb8(fb: &Framebuffer) -> Vec<u8> {
+    let mut result = Vec::with_capacity(fb.width() * fb.height() * 3);
+
+    for y in 0..fb.height() {
+        for x in 0..fb.width() {
+            let mut col = fb.pixel(x, y);
+            col.gamma2_correct();
+
+            for c in 0..3 {
+                result.push((255.99 * col[c]).clamp(0.0, 255.0) as u8);
+            }
+        }
+    }
+
+    result
+}
+
+/// Picks the writer from the extension of `path`. High dynamic range
+/// formats get the linear radiance, OpenEXR channels are half floats if
+/// `half` is set.
+pub fn writer_for_path(path: &Path, half: bool) -> Result<Box<dyn ImageWriter>, String> {
+    let ext = path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase());
+
+    match ext.as_deref() {
+        Some("png") => Ok(Box::new(PngWriter {})),
+        Some("ppm") => Ok(Box::new(PpmWriter { ascii: false })),
+        Some("hdr") => Ok(Box::new(HdrWriter {})),
+        Some("pfm") => Ok(Box::new(PfmWriter {})),
+        Some("exr") => Ok(Box::new(ExrWriter { half })),
+        Some(e) => Err(format!("unsupported image format '{}'", e)),
+        None => Err(format!("{}: missing file extension", path.display())),
+    }
+}
@@ -0,0 +1,30 @@
+use std::io::{self, Write};
+
+use crate::framebuffer::Framebuffer;
+
+use super::ImageWriter;
+
+/// Portable float map, little endian
+pub struct PfmWriter {}
+
+impl ImageWriter for PfmWriter {
+    fn write(&self, fb: &Framebuffer, out: &mut dyn Write) -> io::Result<()> {
+        // a negative scale means little endian
+        write!(out, "PF\n{} {}\n-1.0\n", fb.width(), fb.height())?;
+
+        // rows are stored from the bottom of the image
+        let mut line = Vec::with_capacity(fb.width() * 12);
+        for y in (0..fb.height()).rev() {
+            line.clear();
+            for x in 0..fb.width() {
+                let col = fb.pixel(x, y);
+                for c in 0..3 {
+                    line.extend_from_slice(&col[c].to_le_bytes());
+                }
+            }
+            out.write_all(&line)?;
+        }
+
+        out.flush()
+    }
+}
@@ -0,0 +1,22 @@
+use std::io::{self, Write};
+
+use png::{BitDepth, ColorType, Encoder};
+
+use crate::framebuffer::Framebuffer;
+
+use super::{ImageWriter, encode_rgb8};
+
+pub struct PngWriter {}
+
+impl ImageWriter for PngWriter {
+    fn write(&self, fb: &Framebuffer, out: &mut dyn Write) -> io::Result<()> {
+        let mut encoder = Encoder::new(&mut *out, fb.width() as u32, fb.height() as u32);
+        encoder.set_color(ColorType::Rgb);
+        encoder.set_depth(BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&encode_rgb8(fb))?;
+        writer.finish()?;
+        out.flush()
+    }
+}
@@ -0,0 +1,26 @@
+use std::io::{self, Write};
+
+use crate::framebuffer::Framebuffer;
+
+use super::{ImageWriter, encode_rgb8};
+
+/// PPM, binary (P6) or plain text (P3) with one pixel per line
+pub struct PpmWriter {
+    pub ascii: bool,
+}
+
+impl ImageWriter for PpmWriter {
+    fn write(&self, fb: &Framebuffer, out: &mut dyn Write) -> io::Result<()> {
+        let pixels = encode_rgb8(fb);
+        if self.ascii {
+            write!(out, "P3\n{} {}\n255\n", fb.width(), fb.height())?;
+            for p in pixels.chunks(3) {
+                writeln!(out, "{} {} {}", p[0], p[1], p[2])?;
+            }
+        } else {
+            write!(out, "P6\n{} {}\n255\n", fb.width(), fb.height())?;
+            out.write_all(&pixels)?;
+        }
+        out.flush()
+    }
+}
@@ -0,0 +1,143 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+use crate::hitable::Hitable;
+use crate::onb::Onb;
+use crate::random;
+
+/// Probability density over directions, with respect to solid angle
+pub trait Pdf<T: Float+MulAssign> {
+    fn value(&self, direction: &Vec3<T>) -> T;
+
+    /// Random direction distributed according to the density
+    fn generate(&self) -> Vec3<T>;
+}
+
+pub fn random_float<T: Float>() -> T {
+    T::from(random::gen::<f32>()).unwrap()
+}
+
+fn pi<T: Float>() -> T {
+    T::from(std::f64::consts::PI).unwrap()
+}
+
+/// Random direction around the z axis, with density cos(theta) / pi
+pub fn random_cosine_direction<T: Float+MulAssign>() -> Vec3<T> {
+    let r1 = random_float::<T>();
+    let r2 = random_float::<T>();
+    let phi = (T::one() + T::one()) * pi::<T>() * r1;
+    let (sin_phi, cos_phi) = phi.sin_cos();
+    Vec3::new(Some([
+        cos_phi * r2.sqrt(),
+        sin_phi * r2.sqrt(),
+        (T::one() - r2).sqrt(),
+    ]))
+}
+
+/// Converts the density of a point picked uniformly on a surface of
+/// `area`, hit at `t` along `direction` with `normal`, to solid angle
+pub fn solid_angle_pdf<T: Float+MulAssign>(direction: &Vec3<T>, t: T, normal: &Vec3<T>, area: T) -> T {
+    let distance_squared = t * t * direction.squared_length();
+    let cosine = Vec3::dot(direction, normal).abs() / direction.length();
+    if cosine <= T::zero() {
+        return T::zero();
+    }
+    distance_squared / (cosine * area)
+}
+
+/// Cosine weighted directions on the hemisphere around a normal
+pub struct CosinePdf<T: Float+MulAssign> {
+    uvw: Onb<T>,
+}
+
+impl<T: Float+MulAssign> CosinePdf<T> {
+    pub fn new(normal: &Vec3<T>) -> CosinePdf<T> {
+        CosinePdf { uvw: Onb::from_w(normal) }
+    }
+}
+
+impl<T: Float+MulAssign> Pdf<T> for CosinePdf<T> {
+    fn value(&self, direction: &Vec3<T>) -> T {
+        let cosine = Vec3::dot(&Vec3::unit_vector(*direction), &self.uvw.w);
+        if cosine > T::zero() { cosine / pi() } else { T::zero() }
+    }
+
+    fn generate(&self) -> Vec3<T> {
+        self.uvw.local(&random_cosine_direction())
+    }
+}
+
+/// Uniform directions on the whole sphere
+pub struct SpherePdf;
+
+impl<T: Float+MulAssign> Pdf<T> for SpherePdf {
+    fn value(&self, _direction: &Vec3<T>) -> T {
+        T::one() / (T::from(4.0).unwrap() * pi())
+    }
+
+    fn generate(&self) -> Vec3<T> {
+        Vec3::unit_vector(Vec3::random_in_unit_sphere())
+    }
+}
+
+/// Directions from `origin` towards points of `hitable`
+pub struct HitablePdf<'a, T: Float+MulAssign> {
+    pub hitable: &'a dyn Hitable<T>,
+    pub origin: Vec3<T>,
+}
+
+impl<'a, T: Float+MulAssign> Pdf<T> for HitablePdf<'a, T> {
+    fn value(&self, direction: &Vec3<T>) -> T {
+        self.hitable.pdf_value(&self.origin, direction)
+    }
+
+    fn generate(&self) -> Vec3<T> {
+        self.hitable.random(&self.origin)
+    }
+}
+
+/// Picks one of several densities, each with its probability. The
+/// probabilities must add up to one.
+pub struct MixturePdf<T: Float+MulAssign> {
+    pub pdfs: Vec<(T, Box<dyn Pdf<T>>)>,
+}
+
+impl<T: Float+MulAssign> Pdf<T> for MixturePdf<T> {
+    fn value(&self, direction: &Vec3<T>) -> T {
+        self.pdfs.iter().fold(T::zero(), |sum, (p, pdf)| sum + *p * pdf.value(direction))
+    }
+
+    fn generate(&self) -> Vec3<T> {
+        let mut x = random_float::<T>();
+        for (p, pdf) in &self.pdfs {
+            if x < *p {
+                return pdf.generate();
+            }
+            x = x - *p;
+        }
+        // rounding, the probabilities add up to one
+        self.pdfs[self.pdfs.len() - 1].1.generate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixture_weighs_its_densities() {
+        let normal = Vec3::new(Some([0.0, 0.0, 1.0]));
+        let mixture: MixturePdf<f32> = MixturePdf {
+            pdfs: vec![(0.25, Box::new(CosinePdf::new(&normal))), (0.75, Box::new(SpherePdf))],
+        };
+        let up = mixture.value(&normal);
+        let expected = 0.25 / std::f32::consts::PI + 0.75 / (4.0 * std::f32::consts::PI);
+        assert!((up - expected).abs() < 1e-6);
+        // only the sphere reaches below the surface
+        assert!((mixture.value(&-normal) - 0.75 / (4.0 * std::f32::consts::PI)).abs() < 1e-6);
+
+        let below = (0..10_000).filter(|_| mixture.generate()[2] < 0.0).count();
+        assert!((below as f32 / 10_000.0 - 0.375).abs() < 0.02, "{}", below);
+    }
+}
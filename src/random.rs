@@ -0,0 +1,19 @@
+use std::cell::RefCell;
+
+use rand::{Rng, FromEntropy, SeedableRng};
+use rand::rngs::SmallRng;
+use rand::distributions::{Distribution, Standard};
+
+thread_local! {
+    static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::from_entropy());
+}
+
+/// Reseeds the random number generator of the current thread, renders
+/// are reproducible as long as every tile starts from a known seed.
+pub fn seed(seed: u64) {
+    RNG.with(|r| *r.borrow_mut() = SmallRng::seed_from_u64(seed));
+}
+
+pub fn gen<T>() -> T where Standard: Distribution<T> {
+    RNG.with(|r| r.borrow_mut().gen())
+}
@@ -2,10 +2,15 @@ use std::ops::MulAssign;
 use num_traits::Float;
 
 use crate::vec3::Vec3;
+use crate::spectrum::Wavelengths;
 
 pub struct Ray<T: Float+MulAssign> {
     pub origin: Vec3<T>,
     pub direction: Vec3<T>,
+    // when the ray was cast, within the camera shutter interval
+    pub time: T,
+    // the wavelengths a path follows in spectral mode, None in RGB
+    pub wavelengths: Option<Wavelengths>,
 }
 
 impl<T: Float+MulAssign> Ray<T> {
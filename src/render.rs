@@ -0,0 +1,275 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::f32;
+
+use crate::ray::Ray;
+use crate::vec3::Vec3;
+use crate::hitable::{Hitable, HitResult};
+use crate::hitablelist::HitableList;
+use crate::pdf::{Pdf, HitablePdf};
+use crate::light::Light;
+use crate::camera::Camera;
+use crate::random;
+use crate::framebuffer::Framebuffer;
+use crate::background::Background;
+use crate::spectrum::{self, Wavelengths};
+
+pub struct RenderSettings {
+    pub width: usize,
+    pub height: usize,
+    pub samples: u32,
+    // trace a few wavelengths per sample rather than red, green and blue
+    pub spectral: bool,
+    pub max_depth: u32,
+    // tiles are seeded from this, when given
+    pub seed: Option<u64>,
+    pub threads: usize,
+    pub tile_size: usize,
+}
+
+struct Tile {
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
+}
+
+/// Everything rays can interact with
+pub struct World<'a> {
+    pub objects: &'a dyn Hitable<f32>,
+    // emitting objects, also part of `objects`, sampled with shadow rays
+    // at every diffuse hit
+    pub lights: &'a HitableList<f32>,
+    // point, spot and directional lights
+    pub punctual_lights: &'a [Box<dyn Light<f32>>],
+    pub background: &'a Background,
+}
+
+// weight of a sample taken with density `a`, against another strategy
+// with density `b`
+fn power_heuristic(a: f32, b: f32) -> f32 {
+    let (a2, b2) = (a*a, b*b);
+    if a2 + b2 > 0.0 { a2 / (a2 + b2) } else { 0.0 }
+}
+
+fn is_black(c: &Vec3<f32>) -> bool {
+    c.e.iter().all(|&x| x <= 0.0)
+}
+
+// light reaching the hit point `t` through a direction sampled on the
+// lights, weighted against picking the same direction with `pdf`
+fn sample_lights(ray: &Ray<f32>, t: &HitResult<f32>, pdf: &dyn Pdf<f32>, world: &World) -> Vec3<f32> {
+    let zero = Vec3::new(None);
+    let light = HitablePdf {
+        hitable: world.lights,
+        origin: t.rec.p,
+    };
+    let direction = light.generate();
+    let light_pdf = light.value(&direction);
+    if light_pdf <= 0.0 {
+        return zero;
+    }
+
+    let shadow = Ray {
+        origin: t.rec.p,
+        direction,
+        time: ray.time,
+        wavelengths: ray.wavelengths,
+    };
+    let f = t.material.eval(ray, &t.rec, &shadow);
+    if is_black(&f) {
+        return zero;
+    }
+
+    // whatever is hit first, an occluder emits nothing
+    match world.objects.hit(&shadow, 0.001, f32::MAX) {
+        Some(h) => {
+            let emitted = spectrum::illuminant(&shadow.wavelengths, h.material.emitted(h.rec.u, h.rec.v, &h.rec.p));
+            let weight = power_heuristic(light_pdf, pdf.value(&direction));
+            emitted * f * (weight / light_pdf)
+        },
+        None => zero,
+    }
+}
+
+// light from the background through a direction picked by the background
+// itself, weighted against picking the same direction with `pdf`
+fn sample_background(ray: &Ray<f32>, t: &HitResult<f32>, pdf: &dyn Pdf<f32>, world: &World) -> Vec3<f32> {
+    let zero = Vec3::new(None);
+    let (direction, light_pdf) = match world.background.sample() {
+        Some(s) => s,
+        None => return zero,
+    };
+    if light_pdf <= 0.0 {
+        return zero;
+    }
+
+    let shadow = Ray {
+        origin: t.rec.p,
+        direction,
+        time: ray.time,
+        wavelengths: ray.wavelengths,
+    };
+    let f = t.material.eval(ray, &t.rec, &shadow);
+    if is_black(&f) || world.objects.hit(&shadow, 0.001, f32::MAX).is_some() {
+        return zero;
+    }
+
+    let weight = power_heuristic(light_pdf, pdf.value(&direction));
+    spectrum::illuminant(&shadow.wavelengths, world.background.color(&shadow)) * f * (weight / light_pdf)
+}
+
+// light reaching the hit point `t` from punctual lights, which can only
+// be found with shadow rays
+fn sample_punctual_lights(ray: &Ray<f32>, t: &HitResult<f32>, world: &World) -> Vec3<f32> {
+    let mut result = Vec3::new(None);
+    for light in world.punctual_lights {
+        let ls = match light.sample(&t.rec.p) {
+            Some(ls) => ls,
+            None => continue,
+        };
+        let shadow = Ray {
+            origin: t.rec.p,
+            direction: ls.direction,
+            time: ray.time,
+            wavelengths: ray.wavelengths,
+        };
+        let f = t.material.eval(ray, &t.rec, &shadow);
+        if is_black(&f) || world.objects.hit(&shadow, 0.001, ls.distance).is_some() {
+            continue;
+        }
+        result += spectrum::illuminant(&shadow.wavelengths, ls.irradiance) * f;
+    }
+    result
+}
+
+// `bsdf_pdf` is the density the ray was sampled with by the material at
+// its origin, None for camera rays and specular bounces whose emission
+// cannot be found by light sampling
+fn color(ray: &Ray<f32>, world: &World, depth: u32, max_depth: u32, bsdf_pdf: Option<f32>) -> Vec3<f32> {
+    let sample_lights_enabled = !world.lights.list.is_empty();
+
+    // 0.001 to avoid shadow acne
+    match &world.objects.hit(ray, 0.001, f32::MAX) {
+        Some(t) => {
+            let mut emitted = spectrum::illuminant(&ray.wavelengths, t.material.emitted(t.rec.u, t.rec.v, &t.rec.p));
+            if let Some(bsdf_pdf) = bsdf_pdf {
+                if sample_lights_enabled && !is_black(&emitted) {
+                    let light_pdf = world.lights.pdf_value(&ray.origin, &ray.direction);
+                    emitted *= power_heuristic(bsdf_pdf, light_pdf);
+                }
+            }
+            if depth >= max_depth {
+                return emitted;
+            }
+
+            let sr = match t.material.scatter(ray, &t.rec) {
+                Some(sr) => sr,
+                None => return emitted,
+            };
+            if sr.is_specular() {
+                return emitted + sr.attenuation * color(&sr.scattered, world, depth+1, max_depth, None);
+            }
+            let pdf = sr.pdf.as_deref().unwrap();
+
+            let mut result = emitted;
+            if sample_lights_enabled {
+                result += sample_lights(ray, t, pdf, world);
+            }
+            result += sample_background(ray, t, pdf, world);
+            result += sample_punctual_lights(ray, t, world);
+
+            let pdf_value = pdf.value(&sr.scattered.direction);
+            if pdf_value > 0.0 && !is_black(&sr.attenuation) {
+                let incoming = color(&sr.scattered, world, depth+1, max_depth, Some(pdf_value));
+                result += sr.attenuation * incoming;
+            }
+            result
+        },
+        None => {
+            let background = spectrum::illuminant(&ray.wavelengths, world.background.color(ray));
+            match bsdf_pdf {
+                Some(bsdf_pdf) => background * power_heuristic(bsdf_pdf, world.background.pdf_value(&ray.direction)),
+                None => background,
+            }
+        },
+    }
+}
+
+fn render_tile(idx: usize, tile: &Tile, world: &World, camera: &Camera<f32>, settings: &RenderSettings) -> Vec<Vec3<f32>> {
+    if let Some(seed) = settings.seed {
+        random::seed(seed ^ (idx as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    }
+    let mut result = Vec::with_capacity((tile.x1 - tile.x0) * (tile.y1 - tile.y0));
+
+    for j in tile.y0..tile.y1 {
+        for i in tile.x0..tile.x1 {
+            let mut col = Vec3::new(Some([0.0, 0.0, 0.0]));
+
+            for _ in 0..settings.samples {
+                let u = (i as f32 + (random::gen::<f32>())) / (settings.width as f32);
+                let v = (j as f32 + (random::gen::<f32>())) / (settings.height as f32);
+                let mut r = camera.get_ray(u, v);
+                if settings.spectral {
+                    let wavelengths = Wavelengths::sample();
+                    r.wavelengths = Some(wavelengths);
+                    col += wavelengths.rgb(&color(&r, world, 0, settings.max_depth, None));
+                } else {
+                    col += color(&r, world, 0, settings.max_depth, None);
+                }
+            }
+
+            result.push(col);
+        }
+    }
+
+    result
+}
+
+/// Renders the image splitting it in tiles that are picked up by
+/// `settings.threads` workers.
+pub fn render(world: &World, camera: &Camera<f32>, settings: &RenderSettings) -> Framebuffer {
+    let mut tiles = Vec::new();
+    for y0 in (0..settings.height).step_by(settings.tile_size) {
+        for x0 in (0..settings.width).step_by(settings.tile_size) {
+            tiles.push(Tile {
+                x0,
+                y0,
+                x1: (x0 + settings.tile_size).min(settings.width),
+                y1: (y0 + settings.tile_size).min(settings.height),
+            });
+        }
+    }
+
+    let next_tile = AtomicUsize::new(0);
+    let rendered: Vec<(usize, Vec<Vec3<f32>>)> = thread::scope(|s| {
+        let workers: Vec<_> = (0..settings.threads.max(1)).map(|_| {
+            s.spawn(|| {
+                let mut done = Vec::new();
+                loop {
+                    let idx = next_tile.fetch_add(1, Ordering::Relaxed);
+                    if idx >= tiles.len() {
+                        break done;
+                    }
+                    done.push((idx, render_tile(idx, &tiles[idx], world, camera, settings)));
+                }
+            })
+        }).collect();
+
+        workers.into_iter().flat_map(|w| w.join().unwrap()).collect()
+    });
+
+    let mut framebuffer = Framebuffer::new(settings.width, settings.height);
+    for (idx, pixels) in rendered {
+        let tile = &tiles[idx];
+        let tile_width = tile.x1 - tile.x0;
+        for (k, col) in pixels.into_iter().enumerate() {
+            let i = tile.x0 + k % tile_width;
+            let j = tile.y0 + k / tile_width;
+            // j grows from the bottom of the image
+            framebuffer.accumulate(i, settings.height - 1 - j, col, settings.samples);
+        }
+    }
+
+    framebuffer
+}
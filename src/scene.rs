@@ -0,0 +1,856 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::f32;
+use std::convert::TryFrom;
+
+use toml::{Table, Value};
+
+use crate::vec3::Vec3;
+use crate::camera::Camera;
+use crate::hitable::Hitable;
+use crate::hitablelist::HitableList;
+use crate::sphere::Sphere;
+use crate::movingsphere::MovingSphere;
+use crate::aarect::{AaRect, BoxShape, Rect};
+use crate::bvh::Bvh;
+use crate::instance::Instance;
+use crate::constantmedium::ConstantMedium;
+use crate::transform::Transform;
+use crate::triangle::Triangle;
+use crate::material::{Material, Lambertian, Metal, Dielectric, DiffuseLight, Isotropic, Conductor, RoughDielectric, Principled};
+use crate::material::Dispersion;
+use crate::background::Background;
+use crate::environment::EnvironmentMap;
+use crate::sky::DaylightSky;
+use crate::light::{Light, PointLight, SpotLight, DirectionalLight};
+use crate::texture::{Texture, ConstantTexture, CheckerTexture, UvCheckerTexture, ImageTexture, WrapMode};
+use crate::texture::{Perlin, NoiseTexture, MarbleTexture, WoodTexture};
+use crate::obj::load_obj;
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(PathBuf, std::io::Error),
+    Syntax(PathBuf, String),
+    Invalid {
+        // the offending section or object, e.g. `objects[3] "ground"`
+        context: String,
+        field: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            SceneError::Syntax(path, e) => write!(f, "{}: {}", path.display(), e),
+            SceneError::Invalid { context, field, message } => write!(f, "{}: field '{}': {}", context, field, message),
+        }
+    }
+}
+
+impl Error for SceneError {}
+
+/// Camera parameters, as taken by `Camera::new` and `Camera::with_shutter`.
+/// `vfov` is in degrees.
+#[derive(Clone)]
+pub struct CameraParams {
+    pub lookfrom: Vec3<f32>,
+    pub lookat: Vec3<f32>,
+    pub vup: Vec3<f32>,
+    pub vfov: f32,
+    pub aperture: f32,
+    pub focus_dist: f32,
+    pub shutter_open: f32,
+    pub shutter_close: f32,
+}
+
+impl Default for CameraParams {
+    fn default() -> CameraParams {
+        CameraParams {
+            lookfrom: Vec3::new(Some([13.0, 2.0, 3.0])),
+            lookat: Vec3::new(Some([0.0, 0.0, 0.0])),
+            vup: Vec3::new(Some([0.0, 1.0, 0.0])),
+            vfov: 20.0,
+            aperture: 0.1,
+            focus_dist: 10.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+        }
+    }
+}
+
+impl CameraParams {
+    pub fn to_camera(&self, aspect: f32) -> Camera<f32> {
+        Camera::new(
+            self.lookfrom,
+            self.lookat,
+            self.vup,
+            self.vfov.to_radians(),
+            aspect,
+            self.aperture,
+            self.focus_dist
+        ).with_shutter(self.shutter_open, self.shutter_close)
+    }
+}
+
+pub struct Scene {
+    pub width: usize,
+    pub height: usize,
+    pub samples: u32,
+    // trace wavelengths rather than red, green and blue
+    pub spectral: bool,
+    pub camera: CameraParams,
+    pub background: Background,
+    pub world: HitableList<f32>,
+    // emitting objects of the world that can be sampled
+    pub lights: HitableList<f32>,
+    pub punctual_lights: Vec<Box<dyn Light<f32>>>,
+}
+
+impl Scene {
+    pub fn camera(&self) -> Camera<f32> {
+        self.camera.to_camera((self.width as f32)/(self.height as f32))
+    }
+}
+
+/// Walks a table keeping track of where it is in the scene file, so that
+/// errors can name the offending object and field.
+struct Section<'a> {
+    context: String,
+    table: &'a Table,
+}
+
+impl<'a> Section<'a> {
+    fn error<E>(&self, field: &str, message: String) -> Result<E, SceneError> {
+        Err(SceneError::Invalid {
+            context: self.context.clone(),
+            field: field.to_string(),
+            message,
+        })
+    }
+
+    fn get(&self, field: &str) -> Result<&'a Value, SceneError> {
+        match self.table.get(field) {
+            Some(v) => Ok(v),
+            None => self.error(field, "missing".to_string()),
+        }
+    }
+
+    fn float(&self, field: &str) -> Result<f32, SceneError> {
+        match self.get(field)? {
+            Value::Float(x) => Ok(*x as f32),
+            Value::Integer(x) => Ok(*x as f32),
+            v => self.error(field, format!("expected a number, found {}", v.type_str())),
+        }
+    }
+
+    fn float_or(&self, field: &str, default: f32) -> Result<f32, SceneError> {
+        if self.table.contains_key(field) { self.float(field) } else { Ok(default) }
+    }
+
+    fn positive_float(&self, field: &str) -> Result<f32, SceneError> {
+        let x = self.float(field)?;
+        if x <= 0.0 {
+            return self.error(field, format!("expected a positive number, found {}", x));
+        }
+        Ok(x)
+    }
+
+    // a number in [0, 1]
+    fn unit_float_or(&self, field: &str, default: f32) -> Result<f32, SceneError> {
+        let x = self.float_or(field, default)?;
+        if !(0.0..=1.0).contains(&x) {
+            return self.error(field, format!("expected a number between 0 and 1, found {}", x));
+        }
+        Ok(x)
+    }
+
+    fn integer_or(&self, field: &str, default: usize) -> Result<usize, SceneError> {
+        match self.table.get(field) {
+            None => Ok(default),
+            Some(Value::Integer(x)) if *x > 0 => Ok(*x as usize),
+            Some(v) => self.error(field, format!("expected a positive integer, found {}", v)),
+        }
+    }
+
+    fn seed_or(&self, field: &str, default: u64) -> Result<u64, SceneError> {
+        match self.table.get(field) {
+            None => Ok(default),
+            Some(Value::Integer(x)) if *x >= 0 => Ok(*x as u64),
+            Some(v) => self.error(field, format!("expected a non negative integer, found {}", v)),
+        }
+    }
+
+    fn string(&self, field: &str) -> Result<&'a str, SceneError> {
+        match self.get(field)? {
+            Value::String(s) => Ok(s),
+            v => self.error(field, format!("expected a string, found {}", v.type_str())),
+        }
+    }
+
+    fn vec3_from(&self, field: &str, v: &Value) -> Result<Vec3<f32>, SceneError> {
+        let err = || self.error(field, format!("expected an array of 3 numbers, found {}", v));
+        let a = match v {
+            Value::Array(a) if a.len() == 3 => a,
+            _ => return err(),
+        };
+        let mut e = [0.0; 3];
+        for (x, y) in e.iter_mut().zip(a.iter()) {
+            *x = match y {
+                Value::Float(f) => *f as f32,
+                Value::Integer(i) => *i as f32,
+                _ => return err(),
+            };
+        }
+        Ok(Vec3::new(Some(e)))
+    }
+
+    // a [min, max] range
+    fn vec2(&self, field: &str) -> Result<[f32; 2], SceneError> {
+        let v = self.get(field)?;
+        let err = || self.error(field, format!("expected an array of 2 increasing numbers, found {}", v));
+        let a = match v {
+            Value::Array(a) if a.len() == 2 => a,
+            _ => return err(),
+        };
+        let mut e = [0.0; 2];
+        for (x, y) in e.iter_mut().zip(a.iter()) {
+            *x = match y {
+                Value::Float(f) => *f as f32,
+                Value::Integer(i) => *i as f32,
+                _ => return err(),
+            };
+        }
+        if e[0] >= e[1] {
+            return err();
+        }
+        Ok(e)
+    }
+
+    fn vec3(&self, field: &str) -> Result<Vec3<f32>, SceneError> {
+        self.vec3_from(field, self.get(field)?)
+    }
+
+    // a vector that can be normalized
+    fn direction(&self, field: &str) -> Result<Vec3<f32>, SceneError> {
+        let d = self.vec3(field)?;
+        if d.squared_length() <= 0.0 {
+            return self.error(field, "expected a non-zero direction".to_string());
+        }
+        Ok(d)
+    }
+
+    fn vec3_or(&self, field: &str, default: Vec3<f32>) -> Result<Vec3<f32>, SceneError> {
+        if self.table.contains_key(field) { self.vec3(field) } else { Ok(default) }
+    }
+
+    fn section(&self, field: &str) -> Result<Section<'a>, SceneError> {
+        match self.get(field)? {
+            Value::Table(table) => Ok(Section {
+                context: format!("{}.{}", self.context, field),
+                table,
+            }),
+            v => self.error(field, format!("expected a table, found {}", v.type_str())),
+        }
+    }
+}
+
+// a texture is either an array with a constant colour or a table
+fn parse_texture(s: &Section, field: &str, dir: &Path) -> Result<Box<dyn Texture<f32>>, SceneError> {
+    if let Value::Array(_) = s.get(field)? {
+        return Ok(Box::new(ConstantTexture {
+            color: s.vec3(field)?,
+        }));
+    }
+
+    let t = s.section(field)?;
+    match t.string("type")? {
+        "checker" => Ok(Box::new(CheckerTexture {
+            odd: parse_texture(&t, "odd", dir)?,
+            even: parse_texture(&t, "even", dir)?,
+            scale: if t.table.contains_key("scale") { t.positive_float("scale")? } else { 1.0 },
+        })),
+        "uv_checker" => Ok(Box::new(UvCheckerTexture {
+            odd: parse_texture(&t, "odd", dir)?,
+            even: parse_texture(&t, "even", dir)?,
+            frequency: if t.table.contains_key("frequency") { t.positive_float("frequency")? } else { 10.0 },
+        })),
+        "image" => {
+            let wrap = if t.table.contains_key("wrap") {
+                match t.string("wrap")? {
+                    "repeat" => WrapMode::Repeat,
+                    "clamp" => WrapMode::Clamp,
+                    "mirror" => WrapMode::Mirror,
+                    w => return t.error("wrap", format!("unknown wrap mode '{}'", w)),
+                }
+            } else {
+                WrapMode::Repeat
+            };
+            match ImageTexture::load(&dir.join(t.string("path")?), wrap) {
+                Ok(texture) => Ok(Box::new(texture)),
+                Err(e) => t.error("path", e),
+            }
+        },
+        "noise" => Ok(Box::new(NoiseTexture {
+            noise: Perlin::new(t.seed_or("seed", 0)?),
+            scale: t.float_or("scale", 1.0)?,
+            color: t.vec3_or("color", Vec3::new(Some([1.0, 1.0, 1.0])))?,
+        })),
+        "marble" => Ok(Box::new(MarbleTexture {
+            noise: Perlin::new(t.seed_or("seed", 0)?),
+            scale: t.float_or("scale", 1.0)?,
+            turbulence: t.float_or("turbulence", 10.0)?,
+            color: t.vec3_or("color", Vec3::new(Some([1.0, 1.0, 1.0])))?,
+        })),
+        "wood" => Ok(Box::new(WoodTexture {
+            noise: Perlin::new(t.seed_or("seed", 0)?),
+            scale: t.float_or("scale", 4.0)?,
+            turbulence: t.float_or("turbulence", 0.3)?,
+            light: t.vec3_or("light", Vec3::new(Some([0.6, 0.4, 0.2])))?,
+            dark: t.vec3_or("dark", Vec3::new(Some([0.3, 0.15, 0.05])))?,
+        })),
+        x => t.error("type", format!("unknown texture '{}'", x)),
+    }
+}
+
+// a scalar texture is either a number or a texture read from its first
+// channel
+fn parse_scalar_texture(s: &Section, field: &str, dir: &Path, default: f32) -> Result<Box<dyn Texture<f32>>, SceneError> {
+    match s.table.get(field) {
+        None | Some(Value::Float(_)) | Some(Value::Integer(_)) => {
+            let x = s.float_or(field, default)?;
+            Ok(Box::new(ConstantTexture {
+                color: Vec3::new(Some([x, x, x])),
+            }))
+        },
+        Some(_) => parse_texture(s, field, dir),
+    }
+}
+
+// dispersion is either the name of a measured material or a table with
+// the coefficients of a formula
+fn parse_dispersion(s: &Section, field: &str) -> Result<Dispersion, SceneError> {
+    if let Value::String(name) = s.get(field)? {
+        return match Dispersion::preset(name) {
+            Some(d) => Ok(d),
+            None => s.error(field, format!("unknown preset '{}'", name)),
+        };
+    }
+
+    let d = s.section(field)?;
+    match d.string("type")? {
+        "cauchy" => Ok(Dispersion::Cauchy {
+            a: d.positive_float("a")?,
+            b: d.float("b")?,
+        }),
+        "sellmeier" => Ok(Dispersion::Sellmeier {
+            b: d.vec3("b")?.e,
+            c: d.vec3("c")?.e,
+        }),
+        x => d.error("type", format!("unknown dispersion '{}'", x)),
+    }
+}
+
+fn parse_material(s: &Section, dir: &Path) -> Result<Box<dyn Material<f32>>, SceneError> {
+    let m = s.section("material")?;
+    match m.string("type")? {
+        "lambertian" => Ok(Box::new(Lambertian {
+            albedo: parse_texture(&m, "albedo", dir)?,
+        })),
+        "metal" => Ok(Box::new(Metal {
+            albedo: parse_texture(&m, "albedo", dir)?,
+            fuzz: m.unit_float_or("fuzz", 0.0)?,
+        })),
+        "dielectric" => {
+            // spectral renders follow the dispersion, RGB renders use the
+            // index at the d line unless given one
+            let dispersion = if m.table.contains_key("dispersion") { Some(parse_dispersion(&m, "dispersion")?) } else { None };
+            let ref_idx = match dispersion {
+                Some(d) if !m.table.contains_key("ref_idx") => d.nominal_ior(),
+                _ => m.positive_float("ref_idx")?,
+            };
+            // per unit length, clear by default
+            let absorption = m.vec3_or("absorption", Vec3::new(None))?;
+            if absorption.e.iter().any(|&a| a < 0.0) {
+                return m.error("absorption", format!("expected non negative coefficients, found {:?}", absorption.e));
+            }
+            // frosted when given a roughness
+            if m.table.contains_key("roughness") {
+                Ok(Box::new(RoughDielectric {
+                    ref_idx,
+                    absorption,
+                    dispersion,
+                    roughness: parse_scalar_texture(&m, "roughness", dir, 0.0)?,
+                }))
+            } else {
+                Ok(Box::new(Dielectric { ref_idx, absorption, dispersion }))
+            }
+        },
+        "diffuse_light" => Ok(Box::new(DiffuseLight {
+            emit: m.vec3("emit")?,
+        })),
+        "isotropic" => Ok(Box::new(Isotropic {
+            albedo: parse_texture(&m, "albedo", dir)?,
+        })),
+        "conductor" => {
+            // a measured metal or the complex refractive index eta + ik
+            let (eta, k) = if m.table.contains_key("preset") {
+                match Conductor::preset(m.string("preset")?) {
+                    Some(p) => p,
+                    None => return m.error("preset", format!("unknown metal '{}'", m.string("preset")?)),
+                }
+            } else {
+                (m.vec3("eta")?, m.vec3("k")?)
+            };
+            Ok(Box::new(Conductor {
+                eta,
+                k,
+                roughness: parse_scalar_texture(&m, "roughness", dir, 0.0)?,
+            }))
+        },
+        // scalar parameters default to those of a grey plastic
+        "principled" => Ok(Box::new(Principled {
+            base_color: parse_texture(&m, "base_color", dir)?,
+            metallic: parse_scalar_texture(&m, "metallic", dir, 0.0)?,
+            roughness: parse_scalar_texture(&m, "roughness", dir, 0.5)?,
+            specular: parse_scalar_texture(&m, "specular", dir, 0.5)?,
+            specular_tint: parse_scalar_texture(&m, "specular_tint", dir, 0.0)?,
+            anisotropic: parse_scalar_texture(&m, "anisotropic", dir, 0.0)?,
+            sheen: parse_scalar_texture(&m, "sheen", dir, 0.0)?,
+            sheen_tint: parse_scalar_texture(&m, "sheen_tint", dir, 0.5)?,
+            clearcoat: parse_scalar_texture(&m, "clearcoat", dir, 0.0)?,
+            clearcoat_gloss: parse_scalar_texture(&m, "clearcoat_gloss", dir, 1.0)?,
+            transmission: parse_scalar_texture(&m, "transmission", dir, 0.0)?,
+            ior: if m.table.contains_key("ior") { m.positive_float("ior")? } else { 1.5 },
+        })),
+        t => m.error("type", format!("unknown material '{}'", t)),
+    }
+}
+
+fn parse_background(s: &Section, dir: &Path) -> Result<Background, SceneError> {
+    match s.string("type")? {
+        "sky" => Ok(Background::Sky),
+        "color" => Ok(Background::Color(s.vec3("color")?)),
+        "environment" => {
+            // equirectangular .hdr or .exr image, rotation in degrees
+            // around the y axis
+            let path = dir.join(s.string("path")?);
+            let rotation = s.float_or("rotation", 0.0)?;
+            let intensity = if s.table.contains_key("intensity") { s.positive_float("intensity")? } else { 1.0 };
+            match EnvironmentMap::load(&path, rotation, intensity) {
+                Ok(e) => Ok(Background::Environment(Box::new(e))),
+                Err(e) => s.error("path", e),
+            }
+        },
+        "daylight" => {
+            let sun_direction = s.direction("sun_direction")?;
+            if sun_direction[1] < 0.0 {
+                return s.error("sun_direction", "expected the sun above the horizon".to_string());
+            }
+            let turbidity = s.float_or("turbidity", 3.0)?;
+            if !(2.0..=10.0).contains(&turbidity) {
+                return s.error("turbidity", format!("expected between 2 and 10, found {}", turbidity));
+            }
+            let sun_disc = match s.table.get("sun_disc") {
+                None => true,
+                Some(Value::Boolean(b)) => *b,
+                Some(v) => return s.error("sun_disc", format!("expected a boolean, found {}", v)),
+            };
+            let intensity = if s.table.contains_key("intensity") { s.positive_float("intensity")? } else { 1.0 };
+            Ok(Background::Daylight(Box::new(DaylightSky::new(
+                sun_direction,
+                turbidity,
+                s.vec3_or("ground_albedo", Vec3::new(Some([0.3, 0.3, 0.3])))?,
+                sun_disc,
+                intensity,
+            ))))
+        },
+        t => s.error("type", format!("unknown background '{}'", t)),
+    }
+}
+
+// intensities are in W/sr, irradiance in W/m^2
+fn parse_light(s: &Section) -> Result<Box<dyn Light<f32>>, SceneError> {
+    match s.string("type")? {
+        "point" => Ok(Box::new(PointLight {
+            position: s.vec3("position")?,
+            intensity: s.vec3("intensity")?,
+        })),
+        "spot" => {
+            let outer_angle = s.positive_float("outer_angle")?;
+            if outer_angle > 180.0 {
+                return s.error("outer_angle", format!("expected at most 180 degrees, found {}", outer_angle));
+            }
+            let inner_angle = s.float_or("inner_angle", outer_angle)?;
+            if inner_angle < 0.0 || inner_angle > outer_angle {
+                return s.error("inner_angle", format!("expected between 0 and outer_angle, found {}", inner_angle));
+            }
+            Ok(Box::new(SpotLight {
+                position: s.vec3("position")?,
+                direction: s.direction("direction")?,
+                intensity: s.vec3("intensity")?,
+                inner_angle,
+                outer_angle,
+            }))
+        },
+        "directional" => Ok(Box::new(DirectionalLight {
+            direction: s.direction("direction")?,
+            irradiance: s.vec3("irradiance")?,
+        })),
+        t => s.error("type", format!("unknown light '{}'", t)),
+    }
+}
+
+// `transform` is a list of steps applied in order, e.g.
+// [{ rotate_y = 15.0 }, { translate = [265.0, 0.0, 295.0] }]
+fn parse_transform(s: &Section) -> Result<Transform<f32>, SceneError> {
+    let steps = match s.get("transform")? {
+        Value::Array(a) => a,
+        v => return s.error("transform", format!("expected an array of tables, found {}", v.type_str())),
+    };
+
+    let mut result = Transform::identity();
+    for (i, step) in steps.iter().enumerate() {
+        let field = format!("transform[{}]", i);
+        let table = match step {
+            Value::Table(t) if t.len() == 1 => t,
+            v => return s.error(&field, format!("expected a table with a single key, found {}", v)),
+        };
+        let t = Section {
+            context: format!("{}.{}", s.context, field),
+            table,
+        };
+        let next = match table.keys().next().unwrap().as_str() {
+            "translate" => Transform::translate(t.vec3("translate")?),
+            "scale" => match t.get("scale")? {
+                Value::Array(_) => {
+                    // mirroring is fine, flattening cannot be inverted
+                    let k = t.vec3("scale")?;
+                    if k.e.contains(&0.0) {
+                        return t.error("scale", format!("expected non-zero factors, found {:?}", k.e));
+                    }
+                    Transform::scale(k)
+                },
+                _ => {
+                    let k = t.positive_float("scale")?;
+                    Transform::scale(Vec3::new(Some([k, k, k])))
+                },
+            },
+            "rotate_x" => Transform::rotate_x(t.float("rotate_x")?),
+            "rotate_y" => Transform::rotate_y(t.float("rotate_y")?),
+            "rotate_z" => Transform::rotate_z(t.float("rotate_z")?),
+            "rotate" => {
+                let r = t.section("rotate")?;
+                Transform::rotate(r.direction("axis")?, r.float("angle")?)
+            },
+            k => return t.error(k, "unknown transform, expected translate, scale, rotate_x, rotate_y, rotate_z or rotate".to_string()),
+        };
+        result = result.then(&next);
+    }
+
+    Ok(result)
+}
+
+fn parse_object(s: &Section, dir: &Path, prototypes: &HashMap<String, Arc<dyn Hitable<f32>>>) -> Result<Box<dyn Hitable<f32>>, SceneError> {
+    let object: Box<dyn Hitable<f32>> = match s.string("type")? {
+        "sphere" => Box::new(Sphere {
+            center: s.vec3("center")?,
+            radius: s.float("radius")?,
+            material: parse_material(s, dir)?,
+        }),
+        "moving_sphere" => Box::new(MovingSphere {
+            center0: s.vec3("center0")?,
+            center1: s.vec3("center1")?,
+            time0: s.float_or("time0", 0.0)?,
+            time1: s.float_or("time1", 1.0)?,
+            radius: s.float("radius")?,
+            material: parse_material(s, dir)?,
+        }),
+        "rect" => {
+            let a = s.vec2("a")?;
+            let b = s.vec2("b")?;
+            let k = s.float("k")?;
+            let rect = match s.string("plane")? {
+                "xy" => Rect::xy(a[0], a[1], b[0], b[1], k),
+                "xz" => Rect::xz(a[0], a[1], b[0], b[1], k),
+                "yz" => Rect::yz(a[0], a[1], b[0], b[1], k),
+                p => return s.error("plane", format!("expected xy, xz or yz, found '{}'", p)),
+            };
+            let flip = match s.table.get("flip") {
+                None => false,
+                Some(Value::Boolean(f)) => *f,
+                Some(v) => return s.error("flip", format!("expected a boolean, found {}", v)),
+            };
+            Box::new(AaRect {
+                rect: if flip { rect.flipped() } else { rect },
+                material: parse_material(s, dir)?,
+            })
+        },
+        "box" => Box::new(BoxShape::new(
+            s.vec3("min")?,
+            s.vec3("max")?,
+            parse_material(s, dir)?,
+        )),
+        "triangle" => {
+            let vertices = match s.get("vertices")? {
+                Value::Array(a) if a.len() == 3 => a,
+                v => return s.error("vertices", format!("expected an array of 3 points, found {}", v)),
+            };
+            Box::new(Triangle {
+                v0: s.vec3_from("vertices", &vertices[0])?,
+                v1: s.vec3_from("vertices", &vertices[1])?,
+                v2: s.vec3_from("vertices", &vertices[2])?,
+                material: parse_material(s, dir)?,
+            })
+        },
+        "mesh" => {
+            // materials come from the MTL files referenced by the OBJ
+            let path = dir.join(s.string("path")?);
+            let mut meshes = match load_obj(&path) {
+                Ok(m) => m,
+                Err(e) => return s.error("path", e.to_string()),
+            };
+            if meshes.len() == 1 {
+                Box::new(meshes.pop().unwrap())
+            } else {
+                Box::new(Bvh::new(HitableList {
+                    list: meshes.into_iter().map(|m| Box::new(m) as Box<dyn Hitable<f32>>).collect(),
+                }))
+            }
+        },
+        "constant_medium" => {
+            // the boundary is only a shape, it shares the material of the
+            // medium unless it has one of its own
+            let b = s.section("boundary")?;
+            let mut table = b.table.clone();
+            if !table.contains_key("material") {
+                table.insert("material".to_string(), s.get("material")?.clone());
+            }
+            let boundary = Section {
+                context: b.context,
+                table: &table,
+            };
+            Box::new(ConstantMedium {
+                boundary: parse_object(&boundary, dir, prototypes)?,
+                density: s.positive_float("density")?,
+                phase_function: parse_material(s, dir)?,
+            })
+        },
+        "instance" => {
+            let name = s.string("prototype")?;
+            let prototype = match prototypes.get(name) {
+                Some(p) => p.clone(),
+                None => return s.error("prototype", format!("unknown prototype '{}'", name)),
+            };
+            let transform = if s.table.contains_key("transform") { parse_transform(s)? } else { Transform::identity() };
+            return Ok(Box::new(Instance::new(prototype, transform)));
+        },
+        t => return s.error("type", format!("unknown object type '{}'", t)),
+    };
+
+    if s.table.contains_key("transform") {
+        return Ok(Box::new(Instance::new(Arc::from(object), parse_transform(s)?)));
+    }
+    Ok(object)
+}
+
+// emitting objects with a shape that lights can be sampled on
+fn is_light(s: &Section, light_prototypes: &HashSet<String>) -> Result<bool, SceneError> {
+    Ok(match s.string("type")? {
+        "sphere" | "rect" | "box" | "triangle" => s.section("material")?.string("type")? == "diffuse_light",
+        "instance" => light_prototypes.contains(s.string("prototype")?),
+        _ => false,
+    })
+}
+
+// entries of an array of tables, named after their `name` when they have one
+fn parse_objects<'a>(root: &Section<'a>, field: &str) -> Result<Vec<Section<'a>>, SceneError> {
+    let objects = match root.table.get(field) {
+        Some(Value::Array(a)) => a.as_slice(),
+        Some(v) => return root.error(field, format!("expected an array of tables, found {}", v.type_str())),
+        None => &[],
+    };
+
+    let mut result = Vec::with_capacity(objects.len());
+    for (i, o) in objects.iter().enumerate() {
+        let table = match o {
+            Value::Table(t) => t,
+            v => return root.error(field, format!("{}[{}]: expected a table, found {}", field, i, v.type_str())),
+        };
+        let context = match table.get("name") {
+            Some(Value::String(name)) => format!("{}[{}] \"{}\"", field, i, name),
+            _ => format!("{}[{}]", field, i),
+        };
+        result.push(Section { context, table });
+    }
+
+    Ok(result)
+}
+
+fn parse_camera(s: &Section) -> Result<CameraParams, SceneError> {
+    let default = CameraParams::default();
+    Ok(CameraParams {
+        lookfrom: s.vec3_or("lookfrom", default.lookfrom)?,
+        lookat: s.vec3_or("lookat", default.lookat)?,
+        vup: s.vec3_or("vup", default.vup)?,
+        vfov: s.float_or("vfov", default.vfov)?,
+        aperture: s.float_or("aperture", default.aperture)?,
+        focus_dist: s.float_or("focus_dist", default.focus_dist)?,
+        shutter_open: s.float_or("shutter_open", default.shutter_open)?,
+        shutter_close: s.float_or("shutter_close", default.shutter_close)?,
+    })
+}
+
+/// Parses a TOML scene description. Paths of meshes are relative to the
+/// directory of the scene file.
+pub fn parse_scene(contents: &str, path: &Path) -> Result<Scene, SceneError> {
+    let table = contents.parse::<Table>().map_err(|e| SceneError::Syntax(path.to_path_buf(), e.to_string()))?;
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let root = Section {
+        context: "scene".to_string(),
+        table: &table,
+    };
+
+    let mut scene = Scene {
+        width: 1200,
+        height: 800,
+        samples: 50,
+        spectral: false,
+        camera: CameraParams::default(),
+        background: Background::Sky,
+        world: HitableList {
+            list: Vec::new()
+        },
+        lights: HitableList {
+            list: Vec::new()
+        },
+        punctual_lights: Vec::new(),
+    };
+
+    if root.table.contains_key("image") {
+        let image = root.section("image")?;
+        scene.width = image.integer_or("width", scene.width)?;
+        scene.height = image.integer_or("height", scene.height)?;
+        let samples = image.integer_or("samples", scene.samples as usize)?;
+        scene.samples = match u32::try_from(samples) {
+            Ok(s) => s,
+            Err(_) => return image.error("samples", format!("expected at most {}, found {}", u32::MAX, samples)),
+        };
+        scene.spectral = match image.table.get("spectral") {
+            None => false,
+            Some(Value::Boolean(b)) => *b,
+            Some(v) => return image.error("spectral", format!("expected a boolean, found {}", v)),
+        };
+    }
+    if root.table.contains_key("camera") {
+        scene.camera = parse_camera(&root.section("camera")?)?;
+    }
+    if root.table.contains_key("background") {
+        scene.background = parse_background(&root.section("background")?, dir)?;
+    }
+
+    // prototypes are not rendered by themselves, only through instances
+    let mut prototypes: HashMap<String, Arc<dyn Hitable<f32>>> = HashMap::new();
+    let mut light_prototypes = HashSet::new();
+    for p in parse_objects(&root, "prototypes")? {
+        let name = p.string("name")?.to_string();
+        let object = parse_object(&p, dir, &prototypes)?;
+        if is_light(&p, &light_prototypes)? {
+            light_prototypes.insert(name.clone());
+        }
+        prototypes.insert(name, Arc::from(object));
+    }
+
+    for o in parse_objects(&root, "objects")? {
+        let object = parse_object(&o, dir, &prototypes)?;
+        if is_light(&o, &light_prototypes)? {
+            let object: Arc<dyn Hitable<f32>> = Arc::from(object);
+            scene.lights.list.push(Box::new(object.clone()));
+            scene.world.list.push(Box::new(object));
+        } else {
+            scene.world.list.push(object);
+        }
+    }
+
+    for l in parse_objects(&root, "lights")? {
+        scene.punctual_lights.push(parse_light(&l)?);
+    }
+
+    Ok(scene)
+}
+
+pub fn load_scene(path: &Path) -> Result<Scene, SceneError> {
+    let contents = fs::read_to_string(path).map_err(|e| SceneError::Io(path.to_path_buf(), e))?;
+    parse_scene(&contents, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(contents: &str) -> String {
+        match parse_scene(contents, Path::new("test.toml")) {
+            Ok(_) => panic!("parsed invalid scene"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    #[test]
+    fn errors_name_the_object_and_field() {
+        assert_eq!(
+            error("[[objects]]\nname = \"ball\"\ntype = \"sphere\"\ncenter = [0, 0, 0]\n"),
+            "objects[0] \"ball\": field 'radius': missing");
+        assert_eq!(
+            error("[[objects]]\ntype = \"sphere\"\ncenter = [0, 0]\nradius = 1.0\n"),
+            "objects[0]: field 'center': expected an array of 3 numbers, found [0, 0]");
+        assert_eq!(
+            error("[[objects]]\ntype = \"cone\"\n"),
+            "objects[0]: field 'type': unknown object type 'cone'");
+        assert_eq!(
+            error("[camera]\nvfov = \"wide\"\n"),
+            "scene.camera: field 'vfov': expected a number, found string");
+    }
+
+    #[test]
+    fn materials_are_checked() {
+        let sphere = "[[objects]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nradius = 1.0\n";
+        assert_eq!(
+            error(&format!("{}material = {{ type = \"plastic\" }}\n", sphere)),
+            "objects[0].material: field 'type': unknown material 'plastic'");
+        assert_eq!(
+            error(&format!("{}material = {{ type = \"metal\", albedo = [0.5, 0.5, 0.5], fuzz = 1.5 }}\n", sphere)),
+            "objects[0].material: field 'fuzz': expected a number between 0 and 1, found 1.5");
+    }
+
+    #[test]
+    fn image_settings_are_checked() {
+        assert_eq!(
+            error("[image]\nwidth = 0\n"),
+            "scene.image: field 'width': expected a positive integer, found 0");
+        assert_eq!(
+            error("[image]\nsamples = 5000000000\n"),
+            "scene.image: field 'samples': expected at most 4294967295, found 5000000000");
+    }
+
+    #[test]
+    fn transforms_are_checked() {
+        let object = "[[objects]]\ntype = \"sphere\"\ncenter = [0, 0, 0]\nradius = 1.0\n\
+            material = { type = \"lambertian\", albedo = [0.5, 0.5, 0.5] }\n";
+        assert_eq!(
+            error(&format!("{}transform = [{{ scale = [1.0, 0.0, 2.0] }}]\n", object)),
+            "objects[0].transform[0]: field 'scale': expected non-zero factors, found [1.0, 0.0, 2.0]");
+        assert_eq!(
+            error(&format!("{}transform = [{{ rotate = {{ axis = [0, 0, 0], angle = 90 }} }}]\n", object)),
+            "objects[0].transform[0].rotate: field 'axis': expected a non-zero direction");
+    }
+
+    #[test]
+    fn syntax_errors_name_the_file() {
+        assert!(error("[image\n").starts_with("test.toml: "));
+    }
+}
@@ -0,0 +1,209 @@
+use std::f32::consts::PI;
+
+use crate::vec3::Vec3;
+use crate::onb::Onb;
+use crate::environment::{EnvironmentMap, equirect_direction};
+use crate::random;
+
+// angular radius of the sun as seen from the earth
+const SUN_RADIUS: f32 = 0.004_65;
+// illuminance of the sun outside of the atmosphere, in klx
+const SUN_ILLUMINANCE: f32 = 128.0;
+// cd/m^2 per W/(m^2 sr), luminance is converted to radiance with it so
+// that the sky combines with the other lights
+const LUMINOUS_EFFICACY: f32 = 683.0;
+// size of the table the sky is importance sampled with
+const TABLE_WIDTH: usize = 128;
+const TABLE_HEIGHT: usize = 64;
+
+/// Daylight sky from "A Practical Analytic Model for Daylight" by
+/// Preetham, Shirley and Smits, with a sun disc. Below the horizon is a
+/// diffuse ground of `ground_albedo` lit by the sky and the sun.
+pub struct DaylightSky {
+    // unit vector towards the sun, above the horizon
+    sun: Vec3<f32>,
+    // Y (kcd/m^2), x and y of the zenith
+    zenith: [f32; 3],
+    // Perez coefficients A to E for Y, x and y
+    perez: [[f32; 5]; 3],
+    ground: Vec3<f32>,
+    // zero without a sun disc
+    sun_radiance: Vec3<f32>,
+    intensity: f32,
+    table: EnvironmentMap,
+    // how often the sun is sampled rather than the table
+    sun_probability: f32,
+}
+
+// 1 - cos(SUN_RADIUS), without the cancellation
+fn sun_one_minus_cos() -> f32 {
+    2.0 * (SUN_RADIUS / 2.0).sin().powi(2)
+}
+
+fn perez(c: &[f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+    (1.0 + c[0] * (c[1] / cos_theta).exp()) * (1.0 + c[2] * (c[3] * gamma).exp() + c[4] * gamma.cos().powi(2))
+}
+
+// linear sRGB from the chromaticity and luminance
+fn xyy_to_rgb(x: f32, y: f32, luminance: f32) -> Vec3<f32> {
+    let cx = x * luminance / y;
+    let cz = (1.0 - x - y) * luminance / y;
+    let cy = luminance;
+    Vec3::new(Some([
+        (3.2406 * cx - 1.5372 * cy - 0.4986 * cz).max(0.0),
+        (-0.9689 * cx + 1.8758 * cy + 0.0415 * cz).max(0.0),
+        (0.0557 * cx - 0.2040 * cy + 1.0570 * cz).max(0.0),
+    ]))
+}
+
+// transmittance of the atmosphere towards the sun for red, green and blue
+// light, from Rayleigh and aerosol scattering
+fn sun_transmittance(theta_s: f32, turbidity: f32) -> Vec3<f32> {
+    // relative optical mass of the air, Kasten's formula
+    let degrees = theta_s.to_degrees();
+    let mass = 1.0 / (theta_s.cos() + 0.15 * (93.885 - degrees).max(0.001).powf(-1.253));
+    let beta = 0.046_08 * turbidity - 0.045_86;
+    let mut t = [0.0; 3];
+    for (t, lambda) in t.iter_mut().zip([0.68f32, 0.55, 0.44].iter()) {
+        let rayleigh = 0.008_735 * lambda.powf(-4.08);
+        let aerosol = beta * lambda.powf(-1.3);
+        *t = (-mass * (rayleigh + aerosol)).exp();
+    }
+    Vec3::new(Some(t))
+}
+
+impl DaylightSky {
+    /// `turbidity` goes from 2 for a very clear sky to 10 for haze, the
+    /// sun disc is optional
+    pub fn new(sun_direction: Vec3<f32>, turbidity: f32, ground_albedo: Vec3<f32>, sun_disc: bool, intensity: f32) -> DaylightSky {
+        let sun = Vec3::unit_vector(sun_direction);
+        let theta_s = sun[1].clamp(0.0, 1.0).acos();
+        let t = turbidity;
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * theta_s);
+        let zenith_y = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let theta = [theta_s.powi(3), theta_s.powi(2), theta_s, 1.0];
+        let chromaticity = |m: [[f32; 4]; 3]| {
+            let row = |r: [f32; 4]| r.iter().zip(theta.iter()).map(|(a, b)| a * b).sum::<f32>();
+            t * t * row(m[0]) + t * row(m[1]) + row(m[2])
+        };
+        let zenith_x = chromaticity([
+            [0.00166, -0.00375, 0.00209, 0.0],
+            [-0.02903, 0.06377, -0.03202, 0.00394],
+            [0.11693, -0.21196, 0.06052, 0.25886],
+        ]);
+        let zenith_y_chromaticity = chromaticity([
+            [0.00275, -0.00610, 0.00317, 0.0],
+            [-0.04214, 0.08970, -0.04153, 0.00516],
+            [0.15346, -0.26756, 0.06670, 0.26688],
+        ]);
+
+        let perez = [
+            [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+            [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+            [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+        ];
+
+        // the sun irradiance spread over its disc
+        let sun_irradiance = sun_transmittance(theta_s, t) * (SUN_ILLUMINANCE * 1000.0 / LUMINOUS_EFFICACY);
+        let sun_solid_angle = 2.0 * PI * sun_one_minus_cos();
+        let sun_radiance = if sun_disc { sun_irradiance / sun_solid_angle } else { Vec3::new(None) };
+
+        let mut sky = DaylightSky {
+            sun,
+            zenith: [zenith_y, zenith_x, zenith_y_chromaticity],
+            perez,
+            ground: Vec3::new(None),
+            sun_radiance,
+            intensity,
+            table: EnvironmentMap::from_pixels(1, 1, vec![Vec3::new(None)], 0.0, 1.0),
+            sun_probability: if sun_disc { 0.5 } else { 0.0 },
+        };
+
+        // the ground reflects the light falling on it from above
+        let mut pixels = Vec::with_capacity(TABLE_WIDTH * TABLE_HEIGHT);
+        let mut irradiance = Vec3::new(None);
+        let pixel_solid_angle = 2.0 * PI * PI / (TABLE_WIDTH * TABLE_HEIGHT) as f32;
+        for y in 0..TABLE_HEIGHT {
+            for x in 0..TABLE_WIDTH {
+                let d = equirect_direction([(x as f32 + 0.5) / TABLE_WIDTH as f32, (y as f32 + 0.5) / TABLE_HEIGHT as f32]);
+                let l = sky.sky_radiance(&d);
+                if d[1] > 0.0 {
+                    let sin_theta = (1.0 - d[1] * d[1]).sqrt();
+                    irradiance += l * (d[1] * sin_theta * pixel_solid_angle);
+                }
+                pixels.push(l);
+            }
+        }
+        if sun_disc {
+            irradiance += sun_irradiance * sun[1];
+        }
+        sky.ground = ground_albedo * irradiance / PI;
+
+        for (i, p) in pixels.iter_mut().enumerate() {
+            let y = i / TABLE_WIDTH;
+            if (y as f32 + 0.5) / TABLE_HEIGHT as f32 > 0.5 {
+                *p = sky.ground;
+            }
+        }
+        sky.table = EnvironmentMap::from_pixels(TABLE_WIDTH, TABLE_HEIGHT, pixels, 0.0, 1.0);
+
+        sky
+    }
+
+    // radiance of the sky alone, without the sun and the intensity
+    fn sky_radiance(&self, direction: &Vec3<f32>) -> Vec3<f32> {
+        let d = Vec3::unit_vector(*direction);
+        if d[1] < 0.0 {
+            return self.ground;
+        }
+
+        // the model is undefined at the horizon
+        let cos_theta = d[1].max(0.001);
+        let cos_gamma = Vec3::dot(&d, &self.sun).clamp(-1.0, 1.0);
+        let gamma = cos_gamma.acos();
+        let theta_s = self.sun[1].clamp(0.0, 1.0).acos();
+
+        let mut v = [0.0; 3];
+        for (i, v) in v.iter_mut().enumerate() {
+            let c = &self.perez[i];
+            *v = self.zenith[i] * perez(c, cos_theta, gamma) / perez(c, 1.0, theta_s);
+        }
+        // Y is in kcd/m^2
+        xyy_to_rgb(v[1], v[2], v[0]) * (1000.0 / LUMINOUS_EFFICACY)
+    }
+
+    fn in_sun(&self, direction: &Vec3<f32>) -> bool {
+        1.0 - Vec3::dot(&Vec3::unit_vector(*direction), &self.sun) <= sun_one_minus_cos()
+    }
+
+    pub fn radiance(&self, direction: &Vec3<f32>) -> Vec3<f32> {
+        let mut l = self.sky_radiance(direction);
+        if self.in_sun(direction) {
+            l += self.sun_radiance;
+        }
+        l * self.intensity
+    }
+
+    /// Direction towards the sun or the sky, with its density
+    pub fn sample(&self) -> (Vec3<f32>, f32) {
+        let direction = if random::gen::<f32>() < self.sun_probability {
+            // uniform in the cone of the sun
+            let z = 1.0 - random::gen::<f32>() * sun_one_minus_cos();
+            let phi = 2.0 * PI * random::gen::<f32>();
+            let r = (1.0 - z * z).max(0.0).sqrt();
+            Onb::from_w(&self.sun).local(&Vec3::new(Some([r * phi.cos(), r * phi.sin(), z])))
+        } else {
+            self.table.sample().0
+        };
+        (direction, self.pdf_value(&direction))
+    }
+
+    pub fn pdf_value(&self, direction: &Vec3<f32>) -> f32 {
+        let mut pdf = (1.0 - self.sun_probability) * self.table.pdf_value(direction);
+        if self.in_sun(direction) {
+            pdf += self.sun_probability / (2.0 * PI * sun_one_minus_cos());
+        }
+        pdf
+    }
+}
@@ -0,0 +1,172 @@
+use crate::vec3::Vec3;
+use crate::random;
+
+// range of the sampled wavelengths, in nm
+const LAMBDA_MIN: f32 = 380.0;
+const LAMBDA_MAX: f32 = 780.0;
+// wavelengths followed by every path, the hero and the ones rotated from it
+const COUNT: usize = 3;
+
+// CIE standard illuminant D65 from 380 to 780 nm, in steps of 10 nm
+const D65: [f32; 41] = [
+    49.98, 54.65, 82.75, 91.49, 93.43, 86.68, 104.86, 117.01, 117.81, 114.86,
+    115.92, 108.81, 109.35, 107.80, 104.79, 107.69, 104.41, 104.05, 100.00, 96.33,
+    95.79, 88.69, 90.01, 89.60, 87.70, 83.29, 83.70, 80.03, 80.21, 82.28,
+    78.28, 69.72, 71.61, 74.35, 61.60, 69.89, 75.09, 63.59, 46.42, 66.81,
+    63.38,
+];
+// integral of D65 times the luminance matching function, so that the
+// illuminant of RGB white has a luminance of one
+const D65_Y: f32 = 10569.34;
+
+// basis spectra of "An RGB to Spectrum Conversion for Reflectances" by
+// Smits, in ten bins from 380 to 720 nm
+const WHITE: [f32; 10] = [1.0000, 1.0000, 0.9999, 0.9993, 0.9992, 0.9998, 1.0000, 1.0000, 1.0000, 1.0000];
+const CYAN: [f32; 10] = [0.9710, 0.9426, 1.0007, 1.0007, 1.0007, 1.0007, 0.1564, 0.0000, 0.0000, 0.0000];
+const MAGENTA: [f32; 10] = [1.0000, 1.0000, 0.9685, 0.2229, 0.0000, 0.0458, 0.8369, 1.0000, 1.0000, 0.9959];
+const YELLOW: [f32; 10] = [0.0001, 0.0000, 0.1088, 0.6651, 1.0000, 1.0000, 0.9996, 0.9586, 0.9685, 0.9840];
+const RED: [f32; 10] = [0.1012, 0.0515, 0.0000, 0.0000, 0.0000, 0.0000, 0.8325, 1.0149, 1.0149, 1.0149];
+const GREEN: [f32; 10] = [0.0000, 0.0000, 0.0273, 0.7937, 1.0000, 0.9418, 0.1719, 0.0000, 0.0000, 0.0025];
+const BLUE: [f32; 10] = [1.0000, 1.0000, 0.8916, 0.3323, 0.0000, 0.0000, 0.0003, 0.0369, 0.0483, 0.0496];
+
+// a tabulated spectrum starting at `start` nm with samples `step` nm apart,
+// linearly interpolated and clamped at its ends
+fn interpolate(table: &[f32], start: f32, step: f32, lambda: f32) -> f32 {
+    let x = ((lambda - start) / step).max(0.0);
+    let i = (x as usize).min(table.len() - 2);
+    let f = (x - i as f32).min(1.0);
+    table[i] * (1.0 - f) + table[i + 1] * f
+}
+
+fn basis(table: &[f32; 10], lambda: f32) -> f32 {
+    // samples at the centres of the bins
+    interpolate(table, 380.0 + 17.0, 34.0, lambda)
+}
+
+fn gaussian(lambda: f32, mean: f32, sigma_below: f32, sigma_above: f32) -> f32 {
+    let sigma = if lambda < mean { sigma_below } else { sigma_above };
+    (-0.5 * ((lambda - mean) / sigma).powi(2)).exp()
+}
+
+/// CIE 1931 colour matching functions, from "Simple Analytic
+/// Approximations to the CIE XYZ Color Matching Functions" by Wyman, Sloan
+/// and Shirley
+fn cie_xyz(lambda: f32) -> [f32; 3] {
+    let l = lambda;
+    [
+        1.056 * gaussian(l, 599.8, 37.9, 31.0) + 0.362 * gaussian(l, 442.0, 16.0, 26.7) - 0.065 * gaussian(l, 501.1, 20.4, 26.2),
+        0.821 * gaussian(l, 568.8, 46.9, 40.5) + 0.286 * gaussian(l, 530.9, 16.3, 31.1),
+        1.217 * gaussian(l, 437.0, 11.8, 36.0) + 0.681 * gaussian(l, 459.0, 26.0, 13.8),
+    ]
+}
+
+// reflectance spectrum of `rgb` at `lambda`, linear in the colour for a
+// given ordering of its channels
+fn smits(rgb: &Vec3<f32>, lambda: f32) -> f32 {
+    let (r, g, b) = (rgb[0], rgb[1], rgb[2]);
+    let s = |table| basis(table, lambda);
+    if r <= g && r <= b {
+        let rest = if g <= b { (g - r) * s(&CYAN) + (b - g) * s(&BLUE) } else { (b - r) * s(&CYAN) + (g - b) * s(&GREEN) };
+        r * s(&WHITE) + rest
+    } else if g <= r && g <= b {
+        let rest = if r <= b { (r - g) * s(&MAGENTA) + (b - r) * s(&BLUE) } else { (b - g) * s(&MAGENTA) + (r - b) * s(&RED) };
+        g * s(&WHITE) + rest
+    } else {
+        let rest = if r <= g { (r - b) * s(&YELLOW) + (g - r) * s(&GREEN) } else { (g - b) * s(&YELLOW) + (r - g) * s(&RED) };
+        b * s(&WHITE) + rest
+    }
+}
+
+/// Wavelengths followed by a path in spectral mode, in nm, from "Hero
+/// Wavelength Spectral Sampling" by Wilkie et al. The hero wavelength is
+/// picked uniformly and the others are rotated from it over the range,
+/// the three channels of colours along the path are then values at these
+/// wavelengths rather than red, green and blue.
+#[derive(Clone, Copy)]
+pub struct Wavelengths {
+    pub lambda: [f32; COUNT],
+    // only the hero wavelength is left, after dispersion
+    pub hero_only: bool,
+}
+
+impl Wavelengths {
+    pub fn sample() -> Wavelengths {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let hero = random::gen::<f32>() * range;
+        let mut lambda = [0.0; COUNT];
+        for (i, l) in lambda.iter_mut().enumerate() {
+            *l = LAMBDA_MIN + (hero + range * i as f32 / COUNT as f32) % range;
+        }
+        Wavelengths { lambda, hero_only: false }
+    }
+
+    pub fn hero(&self) -> f32 {
+        self.lambda[0]
+    }
+
+    /// Weight that keeps only the hero wavelength from here on, for
+    /// scattering whose direction depends on the wavelength, with the
+    /// wavelengths of the scattered ray
+    pub fn terminate_secondary(&self) -> (Vec3<f32>, Wavelengths) {
+        // the hero stands in for all of them
+        let weight = if self.hero_only { 1.0 } else { COUNT as f32 };
+        let wavelengths = Wavelengths { lambda: self.lambda, hero_only: true };
+        (Vec3::new(Some([weight, 0.0, 0.0])), wavelengths)
+    }
+
+    fn map<F: Fn(f32) -> f32>(&self, f: F) -> Vec3<f32> {
+        let mut e = [0.0; COUNT];
+        for (e, &l) in e.iter_mut().zip(self.lambda.iter()) {
+            *e = f(l);
+        }
+        Vec3::new(Some(e))
+    }
+
+    /// Reflectance, or any other ratio, given as linear sRGB
+    pub fn reflectance(&self, rgb: &Vec3<f32>) -> Vec3<f32> {
+        self.map(|l| smits(rgb, l))
+    }
+
+    /// Emitted radiance given as linear sRGB, white is D65 light
+    pub fn illuminant(&self, rgb: &Vec3<f32>) -> Vec3<f32> {
+        self.map(|l| smits(rgb, l) * interpolate(&D65, 380.0, 10.0, l) / D65_Y)
+    }
+
+    /// Linear sRGB of the radiance carried at these wavelengths, through
+    /// CIE XYZ
+    pub fn rgb(&self, radiance: &Vec3<f32>) -> Vec3<f32> {
+        // the density of each wavelength is 1 / range
+        let scale = (LAMBDA_MAX - LAMBDA_MIN) / COUNT as f32;
+        let mut xyz = [0.0; 3];
+        for (i, &l) in self.lambda.iter().enumerate() {
+            let cmf = cie_xyz(l);
+            for (x, c) in xyz.iter_mut().zip(cmf.iter()) {
+                *x += radiance[i] * c * scale;
+            }
+        }
+        let [x, y, z] = xyz;
+        Vec3::new(Some([
+            3.2406 * x - 1.5372 * y - 0.4986 * z,
+            -0.9689 * x + 1.8758 * y + 0.0415 * z,
+            0.0557 * x - 0.2040 * y + 1.0570 * z,
+        ]))
+    }
+}
+
+/// Reflectance given as linear sRGB at the wavelengths of a ray, or as is
+/// when rendering in RGB
+pub fn reflectance(wavelengths: &Option<Wavelengths>, rgb: Vec3<f32>) -> Vec3<f32> {
+    match wavelengths {
+        Some(w) => w.reflectance(&rgb),
+        None => rgb,
+    }
+}
+
+/// Emitted radiance given as linear sRGB at the wavelengths of a ray, or
+/// as is when rendering in RGB
+pub fn illuminant(wavelengths: &Option<Wavelengths>, rgb: Vec3<f32>) -> Vec3<f32> {
+    match wavelengths {
+        Some(w) => w.illuminant(&rgb),
+        None => rgb,
+    }
+}
@@ -6,8 +6,11 @@ use num_traits::Float;
 
 use crate::vec3::Vec3;
 use crate::ray::Ray;
+use crate::aabb::Aabb;
 use crate::hitable::{HitRecord, Hitable, HitResult};
 use crate::material::Material;
+use crate::onb::Onb;
+use crate::pdf::random_float;
 
 pub struct Sphere<T: Float+MulAssign>  {
     pub center: Vec3<T>,
@@ -15,42 +18,122 @@ pub struct Sphere<T: Float+MulAssign>  {
     pub material: Box<dyn Material<T>>,
 }
 
-impl<T: Float+MulAssign> Hitable<T> for Sphere<T> {
-    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<T>> {
-        let oc = r.origin - self.center;
-        let a = Vec3::dot(&r.direction, &r.direction);
-        let b = Vec3::dot(&oc, &r.direction);
-        let c = Vec3::dot(&oc, &oc) - self.radius * self.radius;
-        let discriminant = b*b - a*c;
-    
-        if discriminant > T::zero() {
-            let temp = (- b - discriminant.sqrt())/a;
-            if temp < t_max && temp > t_min {
-                let p = r.point_at_parameter(temp);
-                return Some(HitResult {
-                    rec: HitRecord {
-                        t: temp,
-                        p,
-                        normal: (p - self.center) / self.radius,
-                    },
-                    material: self.material.as_ref(),
-                });
-            }
-
-            let temp = (- b + discriminant.sqrt())/a;
-            if temp < t_max && temp > t_min {
-                let p = r.point_at_parameter(temp);
-                return Some(HitResult {
-                    rec: HitRecord {
-                        t: temp,
-                        p,
-                        normal: (p - self.center) / self.radius,
-                    },
-                    material: self.material.as_ref(),
-                });
-            }
+// u goes around the y axis starting from -x, v from the bottom to the top
+fn sphere_uv<T: Float+MulAssign>(p: Vec3<T>) -> (T, T) {
+    let pi = T::from(std::f64::consts::PI).unwrap();
+    let two = T::one() + T::one();
+    let phi = p[2].atan2(p[0]);
+    let theta = p[1].max(-T::one()).min(T::one()).asin();
+    (T::one() - (phi + pi) / (two * pi), (theta + pi / two) / pi)
+}
+
+pub fn hit_sphere<'a, T: Float+MulAssign>(center: Vec3<T>, radius: T, material: &'a dyn Material<T>, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'a, T>> {
+    let oc = r.origin - center;
+    let a = Vec3::dot(&r.direction, &r.direction);
+    let b = Vec3::dot(&oc, &r.direction);
+    let c = Vec3::dot(&oc, &oc) - radius * radius;
+    let discriminant = b*b - a*c;
+
+    if discriminant > T::zero() {
+        let temp = (- b - discriminant.sqrt())/a;
+        if temp < t_max && temp > t_min {
+            let p = r.point_at_parameter(temp);
+            let (u, v) = sphere_uv((p - center) / radius.abs());
+            return Some(HitResult {
+                rec: HitRecord {
+                    t: temp,
+                    p,
+                    normal: (p - center) / radius,
+                    u,
+                    v,
+                    barycentric: None,
+                },
+                material,
+            });
+        }
+
+        let temp = (- b + discriminant.sqrt())/a;
+        if temp < t_max && temp > t_min {
+            let p = r.point_at_parameter(temp);
+            let (u, v) = sphere_uv((p - center) / radius.abs());
+            return Some(HitResult {
+                rec: HitRecord {
+                    t: temp,
+                    p,
+                    normal: (p - center) / radius,
+                    u,
+                    v,
+                    barycentric: None,
+                },
+                material,
+            });
+        }
+    }
+
+    None
+}
+
+// radius can be negative for hollow glass spheres
+pub fn sphere_bounding_box<T: Float+MulAssign>(center: Vec3<T>, radius: T) -> Aabb<T> {
+    let r = radius.abs();
+    Aabb::new(
+        center - Vec3::new(Some([r, r, r])),
+        center + Vec3::new(Some([r, r, r])),
+    )
+}
+
+// direction within the cone subtended by a sphere of `radius` at squared
+// distance `distance_squared`, around the z axis
+fn random_to_sphere<T: Float+MulAssign>(radius: T, distance_squared: T) -> Vec3<T> {
+    let r1 = random_float::<T>();
+    let r2 = random_float::<T>();
+    let z = T::one() + r2 * ((T::one() - radius * radius / distance_squared).sqrt() - T::one());
+    let phi = (T::one() + T::one()) * T::from(std::f64::consts::PI).unwrap() * r1;
+    let (sin_phi, cos_phi) = phi.sin_cos();
+    let s = (T::one() - z * z).sqrt();
+    Vec3::new(Some([cos_phi * s, sin_phi * s, z]))
+}
+
+impl<T: Float+MulAssign+Send+Sync> Hitable<T> for Sphere<T> {
+    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'_, T>> {
+        hit_sphere(self.center, self.radius, self.material.as_ref(), r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        Some(sphere_bounding_box(self.center, self.radius))
+    }
+
+    // directions are sampled uniformly in the cone of the sphere, or on
+    // the whole sphere of directions from inside it
+    fn pdf_value(&self, origin: &Vec3<T>, direction: &Vec3<T>) -> T {
+        let r = Ray {
+            origin: *origin,
+            direction: *direction,
+            time: T::zero(),
+            wavelengths: None,
+        };
+        if self.hit(&r, T::from(0.001).unwrap(), T::max_value()).is_none() {
+            return T::zero();
         }
 
-        None
+        let pi = T::from(std::f64::consts::PI).unwrap();
+        let two = T::one() + T::one();
+        let radius = self.radius.abs();
+        let distance_squared = (self.center - *origin).squared_length();
+        if distance_squared <= radius * radius {
+            return T::one() / (two * two * pi);
+        }
+        let cos_theta_max = (T::one() - radius * radius / distance_squared).sqrt();
+        T::one() / (two * pi * (T::one() - cos_theta_max))
+    }
+
+    fn random(&self, origin: &Vec3<T>) -> Vec3<T> {
+        let radius = self.radius.abs();
+        let direction = self.center - *origin;
+        let distance_squared = direction.squared_length();
+        if distance_squared <= radius * radius {
+            return Vec3::unit_vector(Vec3::random_in_unit_sphere());
+        }
+        Onb::from_w(&direction).local(&random_to_sphere(radius, distance_squared))
     }
 }
@@ -0,0 +1,42 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+use crate::texture::Texture;
+
+/// Solid checker pattern, alternating every `scale` units along each axis
+pub struct CheckerTexture<T: Float+MulAssign> {
+    pub odd: Box<dyn Texture<T>>,
+    pub even: Box<dyn Texture<T>>,
+    pub scale: T,
+}
+
+/// Checker pattern in surface coordinates, with `frequency` squares along
+/// u and v
+pub struct UvCheckerTexture<T: Float+MulAssign> {
+    pub odd: Box<dyn Texture<T>>,
+    pub even: Box<dyn Texture<T>>,
+    pub frequency: T,
+}
+
+impl Texture<f32> for CheckerTexture<f32> {
+    fn value(&self, u: f32, v: f32, p: &Vec3<f32>) -> Vec3<f32> {
+        let cells = (p[0] / self.scale).floor() + (p[1] / self.scale).floor() + (p[2] / self.scale).floor();
+        if (cells as i64) % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
+
+impl Texture<f32> for UvCheckerTexture<f32> {
+    fn value(&self, u: f32, v: f32, p: &Vec3<f32>) -> Vec3<f32> {
+        let cells = (u * self.frequency).floor() + (v * self.frequency).floor();
+        if (cells as i64) % 2 == 0 {
+            self.even.value(u, v, p)
+        } else {
+            self.odd.value(u, v, p)
+        }
+    }
+}
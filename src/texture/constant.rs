@@ -0,0 +1,15 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+use crate::texture::Texture;
+
+pub struct ConstantTexture<T: Float+MulAssign> {
+    pub color: Vec3<T>,
+}
+
+impl<T: Float+MulAssign+Send+Sync> Texture<T> for ConstantTexture<T> {
+    fn value(&self, _u: T, _v: T, _p: &Vec3<T>) -> Vec3<T> {
+        self.color
+    }
+}
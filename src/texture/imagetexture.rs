@@ -0,0 +1,79 @@
+use std::path::Path;
+
+use crate::vec3::Vec3;
+use crate::texture::Texture;
+
+/// How surface coordinates outside [0, 1] are mapped onto the image
+#[derive(Copy,Clone)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+/// Texture backed by a PNG or JPEG image, sampled with bilinear filtering
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    // linear colours, rows from the top of the image
+    pixels: Vec<Vec3<f32>>,
+    wrap: WrapMode,
+}
+
+impl ImageTexture {
+    pub fn load(path: &Path, wrap: WrapMode) -> Result<ImageTexture, String> {
+        let img = image::open(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))?
+            .to_rgb8();
+
+        // undo the gamma 2 the image was encoded with, as the writers do
+        let pixels = img.pixels()
+            .map(|p| {
+                let c = Vec3::new(Some([p[0] as f32, p[1] as f32, p[2] as f32])) / 255.0;
+                c * c
+            })
+            .collect();
+
+        Ok(ImageTexture {
+            width: img.width() as usize,
+            height: img.height() as usize,
+            pixels,
+            wrap,
+        })
+    }
+
+    fn wrap(&self, i: i64, n: usize) -> usize {
+        let n = n as i64;
+        let i = match self.wrap {
+            WrapMode::Repeat => i.rem_euclid(n),
+            WrapMode::Clamp => i.clamp(0, n - 1),
+            WrapMode::Mirror => {
+                let i = i.rem_euclid(2 * n);
+                if i < n { i } else { 2 * n - 1 - i }
+            },
+        };
+        i as usize
+    }
+
+    fn texel(&self, x: i64, y: i64) -> Vec3<f32> {
+        self.pixels[self.wrap(y, self.height) * self.width + self.wrap(x, self.width)]
+    }
+}
+
+impl Texture<f32> for ImageTexture {
+    fn value(&self, u: f32, v: f32, _p: &Vec3<f32>) -> Vec3<f32> {
+        // texel centers are at half integer coordinates, v = 0 is the
+        // bottom of the image
+        let x = u * self.width as f32 - 0.5;
+        let y = (1.0 - v) * self.height as f32 - 0.5;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+}
@@ -0,0 +1,22 @@
+mod constant;
+mod checker;
+mod imagetexture;
+mod perlin;
+mod noise;
+
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+
+pub use self::constant::ConstantTexture;
+pub use self::checker::{CheckerTexture, UvCheckerTexture};
+pub use self::imagetexture::{ImageTexture, WrapMode};
+pub use self::perlin::Perlin;
+pub use self::noise::{NoiseTexture, MarbleTexture, WoodTexture};
+
+/// Colour of a surface at the hit point with surface coordinates (u, v)
+/// and position p
+pub trait Texture<T: Float+MulAssign>: Send + Sync {
+    fn value(&self, u: T, v: T, p: &Vec3<T>) -> Vec3<T>;
+}
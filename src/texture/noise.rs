@@ -0,0 +1,55 @@
+use crate::vec3::Vec3;
+use crate::texture::Texture;
+use crate::texture::perlin::Perlin;
+
+const TURBULENCE_DEPTH: u32 = 7;
+
+/// Plain Perlin noise scaled to [0, 1]
+pub struct NoiseTexture {
+    pub noise: Perlin,
+    pub scale: f32,
+    pub color: Vec3<f32>,
+}
+
+/// Veins of `color` along z, displaced by turbulence
+pub struct MarbleTexture {
+    pub noise: Perlin,
+    // frequency of both the veins and their turbulence, as for the plain
+    // noise
+    pub scale: f32,
+    pub turbulence: f32,
+    pub color: Vec3<f32>,
+}
+
+/// Growth rings around the y axis, displaced by turbulence
+pub struct WoodTexture {
+    pub noise: Perlin,
+    // rings per unit length, also the frequency of their turbulence
+    pub scale: f32,
+    pub turbulence: f32,
+    pub light: Vec3<f32>,
+    pub dark: Vec3<f32>,
+}
+
+impl Texture<f32> for NoiseTexture {
+    fn value(&self, _u: f32, _v: f32, p: &Vec3<f32>) -> Vec3<f32> {
+        self.color * 0.5 * (1.0 + self.noise.noise(&(*p * self.scale)))
+    }
+}
+
+impl Texture<f32> for MarbleTexture {
+    fn value(&self, _u: f32, _v: f32, p: &Vec3<f32>) -> Vec3<f32> {
+        let phase = self.scale * p[2] + self.turbulence * self.noise.turb(&(*p * self.scale), TURBULENCE_DEPTH);
+        self.color * 0.5 * (1.0 + phase.sin())
+    }
+}
+
+impl Texture<f32> for WoodTexture {
+    fn value(&self, _u: f32, _v: f32, p: &Vec3<f32>) -> Vec3<f32> {
+        let r = (p[0]*p[0] + p[2]*p[2]).sqrt() * self.scale
+            + self.turbulence * self.noise.turb(&(*p * self.scale), TURBULENCE_DEPTH);
+        // sharpen the rings, the late wood is thinner than the early wood
+        let t = (r - r.floor()).powi(3);
+        self.light * (1.0 - t) + self.dark * t
+    }
+}
@@ -0,0 +1,95 @@
+use rand::{Rng, SeedableRng};
+use rand::rngs::SmallRng;
+
+use crate::vec3::Vec3;
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise, see "Ray Tracing: The Next Week". The same seed always
+/// gives the same noise.
+pub struct Perlin {
+    ranvec: Vec<Vec3<f32>>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+fn generate_perm(rng: &mut SmallRng) -> Vec<usize> {
+    let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+    for i in (1..POINT_COUNT).rev() {
+        let target = rng.gen_range(0, i + 1);
+        p.swap(i, target);
+    }
+    p
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Perlin {
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let ranvec = (0..POINT_COUNT)
+            .map(|_| Vec3::unit_vector(Vec3::new(Some([
+                rng.gen_range(-1.0, 1.0),
+                rng.gen_range(-1.0, 1.0),
+                rng.gen_range(-1.0, 1.0),
+            ]))))
+            .collect();
+
+        Perlin {
+            ranvec,
+            perm_x: generate_perm(&mut rng),
+            perm_y: generate_perm(&mut rng),
+            perm_z: generate_perm(&mut rng),
+        }
+    }
+
+    /// Noise in [-1, 1], trilinear interpolation of the gradients at the
+    /// corners of the unit cell containing p, smoothed with a Hermite cubic
+    pub fn noise(&self, p: &Vec3<f32>) -> f32 {
+        let u = p[0] - p[0].floor();
+        let v = p[1] - p[1].floor();
+        let w = p[2] - p[2].floor();
+        let i = p[0].floor() as i64;
+        let j = p[1].floor() as i64;
+        let k = p[2].floor() as i64;
+
+        let uu = u*u*(3.0 - 2.0*u);
+        let vv = v*v*(3.0 - 2.0*v);
+        let ww = w*w*(3.0 - 2.0*w);
+
+        let mut accum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let c = self.ranvec[
+                        self.perm_x[((i + di) & 255) as usize] ^
+                        self.perm_y[((j + dj) & 255) as usize] ^
+                        self.perm_z[((k + dk) & 255) as usize]
+                    ];
+                    let (fi, fj, fk) = (di as f32, dj as f32, dk as f32);
+                    let weight = Vec3::new(Some([u - fi, v - fj, w - fk]));
+                    accum += (fi*uu + (1.0 - fi)*(1.0 - uu))
+                        * (fj*vv + (1.0 - fj)*(1.0 - vv))
+                        * (fk*ww + (1.0 - fk)*(1.0 - ww))
+                        * Vec3::dot(&c, &weight);
+                }
+            }
+        }
+
+        accum
+    }
+
+    /// Sum of `depth` octaves of noise
+    pub fn turb(&self, p: &Vec3<f32>, depth: u32) -> f32 {
+        let mut accum = 0.0;
+        let mut temp_p = *p;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(&temp_p);
+            weight *= 0.5;
+            temp_p *= 2.0;
+        }
+
+        accum.abs()
+    }
+}
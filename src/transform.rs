@@ -0,0 +1,201 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+
+type Matrix<T> = [[T; 4]; 3];
+
+/// Affine transform, stored together with its inverse. The last row of
+/// the matrices is implicitly [0, 0, 0, 1].
+#[derive(Copy,Clone)]
+pub struct Transform<T: Float+MulAssign> {
+    m: Matrix<T>,
+    inv: Matrix<T>,
+}
+
+fn identity<T: Float>() -> Matrix<T> {
+    let (o, z) = (T::one(), T::zero());
+    [
+        [o, z, z, z],
+        [z, o, z, z],
+        [z, z, o, z],
+    ]
+}
+
+// a * b, i.e. b is applied first
+fn mul<T: Float>(a: &Matrix<T>, b: &Matrix<T>) -> Matrix<T> {
+    let mut r = [[T::zero(); 4]; 3];
+    for (i, row) in r.iter_mut().enumerate() {
+        for (j, x) in row.iter_mut().enumerate() {
+            *x = a[i][0]*b[0][j] + a[i][1]*b[1][j] + a[i][2]*b[2][j];
+        }
+        row[3] = row[3] + a[i][3];
+    }
+    r
+}
+
+fn rotation<T: Float>(axis: [T; 3], degrees: T) -> Matrix<T> {
+    let len = (axis[0]*axis[0] + axis[1]*axis[1] + axis[2]*axis[2]).sqrt();
+    let (x, y, z) = (axis[0] / len, axis[1] / len, axis[2] / len);
+    let (s, c) = degrees.to_radians().sin_cos();
+    let t = T::one() - c;
+    let zero = T::zero();
+
+    // Rodrigues' rotation formula
+    [
+        [t*x*x + c, t*x*y - s*z, t*x*z + s*y, zero],
+        [t*x*y + s*z, t*y*y + c, t*y*z - s*x, zero],
+        [t*x*z - s*y, t*y*z + s*x, t*z*z + c, zero],
+    ]
+}
+
+impl<T: Float+MulAssign> Transform<T> {
+    pub fn identity() -> Transform<T> {
+        Transform {
+            m: identity(),
+            inv: identity(),
+        }
+    }
+
+    pub fn translate(d: Vec3<T>) -> Transform<T> {
+        let mut m = identity();
+        let mut inv = identity();
+        for a in 0..3 {
+            m[a][3] = d[a];
+            inv[a][3] = -d[a];
+        }
+        Transform { m, inv }
+    }
+
+    /// Scales by a different factor along each axis, none can be zero
+    pub fn scale(s: Vec3<T>) -> Transform<T> {
+        let mut m = identity();
+        let mut inv = identity();
+        for a in 0..3 {
+            m[a][a] = s[a];
+            inv[a][a] = T::one() / s[a];
+        }
+        Transform { m, inv }
+    }
+
+    /// Rotates counterclockwise around `axis`, looking at it from its tip
+    pub fn rotate(axis: Vec3<T>, degrees: T) -> Transform<T> {
+        Transform {
+            m: rotation(axis.e, degrees),
+            inv: rotation(axis.e, -degrees),
+        }
+    }
+
+    pub fn rotate_x(degrees: T) -> Transform<T> {
+        Transform::rotate(Vec3::new(Some([T::one(), T::zero(), T::zero()])), degrees)
+    }
+
+    pub fn rotate_y(degrees: T) -> Transform<T> {
+        Transform::rotate(Vec3::new(Some([T::zero(), T::one(), T::zero()])), degrees)
+    }
+
+    pub fn rotate_z(degrees: T) -> Transform<T> {
+        Transform::rotate(Vec3::new(Some([T::zero(), T::zero(), T::one()])), degrees)
+    }
+
+    /// This transform followed by `next`
+    pub fn then(&self, next: &Transform<T>) -> Transform<T> {
+        Transform {
+            m: mul(&next.m, &self.m),
+            inv: mul(&self.inv, &next.inv),
+        }
+    }
+
+    pub fn inverse(&self) -> Transform<T> {
+        Transform {
+            m: self.inv,
+            inv: self.m,
+        }
+    }
+
+    /// Determinant of the linear part
+    pub fn determinant(&self) -> T {
+        let m = &self.m;
+        m[0][0] * (m[1][1]*m[2][2] - m[1][2]*m[2][1])
+            - m[0][1] * (m[1][0]*m[2][2] - m[1][2]*m[2][0])
+            + m[0][2] * (m[1][0]*m[2][1] - m[1][1]*m[2][0])
+    }
+
+    pub fn point(&self, p: &Vec3<T>) -> Vec3<T> {
+        let m = &self.m;
+        Vec3::new(Some([
+            m[0][0]*p[0] + m[0][1]*p[1] + m[0][2]*p[2] + m[0][3],
+            m[1][0]*p[0] + m[1][1]*p[1] + m[1][2]*p[2] + m[1][3],
+            m[2][0]*p[0] + m[2][1]*p[1] + m[2][2]*p[2] + m[2][3],
+        ]))
+    }
+
+    pub fn vector(&self, v: &Vec3<T>) -> Vec3<T> {
+        let m = &self.m;
+        Vec3::new(Some([
+            m[0][0]*v[0] + m[0][1]*v[1] + m[0][2]*v[2],
+            m[1][0]*v[0] + m[1][1]*v[1] + m[1][2]*v[2],
+            m[2][0]*v[0] + m[2][1]*v[1] + m[2][2]*v[2],
+        ]))
+    }
+
+    /// Normals transform with the inverse transpose, the result is not
+    /// normalized
+    pub fn normal(&self, n: &Vec3<T>) -> Vec3<T> {
+        let inv = &self.inv;
+        Vec3::new(Some([
+            inv[0][0]*n[0] + inv[1][0]*n[1] + inv[2][0]*n[2],
+            inv[0][1]*n[0] + inv[1][1]*n[1] + inv[2][1]*n[2],
+            inv[0][2]*n[0] + inv[1][2]*n[1] + inv[2][2]*n[2],
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec3<f64>, b: Vec3<f64>) {
+        assert!((a - b).length() < 1e-9, "{:?} != {:?}", a.e, b.e);
+    }
+
+    fn v(x: f64, y: f64, z: f64) -> Vec3<f64> {
+        Vec3::new(Some([x, y, z]))
+    }
+
+    fn example() -> Transform<f64> {
+        Transform::scale(v(2.0, -0.5, 3.0))
+            .then(&Transform::rotate(v(1.0, 2.0, -1.0), 37.0))
+            .then(&Transform::translate(v(4.0, -1.0, 0.5)))
+    }
+
+    #[test]
+    fn inverse_undoes_the_transform() {
+        let t = example();
+        let p = v(0.3, -1.2, 2.5);
+        assert_close(t.inverse().point(&t.point(&p)), p);
+        assert_close(t.point(&t.inverse().point(&p)), p);
+        assert_close(t.inverse().vector(&t.vector(&p)), p);
+        assert!((t.determinant() * t.inverse().determinant() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn transforms_compose_in_order() {
+        let t = Transform::rotate_z(90.0).then(&Transform::translate(v(1.0, 0.0, 0.0)));
+        assert_close(t.point(&v(1.0, 0.0, 0.0)), v(1.0, 1.0, 0.0));
+        // translations leave vectors alone
+        assert_close(t.vector(&v(1.0, 0.0, 0.0)), v(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn normals_stay_perpendicular_to_tangents() {
+        let t = example();
+        let (a, b) = (v(1.0, 0.5, -2.0), v(-0.3, 1.0, 0.7));
+        let n = Vec3::cross(&a, &b);
+        let (ta, tb, tn) = (t.vector(&a), t.vector(&b), t.normal(&n));
+        assert!(Vec3::dot(&tn, &ta).abs() < 1e-9);
+        assert!(Vec3::dot(&tn, &tb).abs() < 1e-9);
+        // so they remain parallel to the normal of the transformed tangents
+        assert!(Vec3::cross(&Vec3::unit_vector(tn), &Vec3::unit_vector(Vec3::cross(&ta, &tb))).length() < 1e-9);
+    }
+}
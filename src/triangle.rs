@@ -0,0 +1,153 @@
+use std::ops::MulAssign;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+use crate::ray::Ray;
+use crate::aabb::Aabb;
+use crate::hitable::{HitRecord, Hitable, HitResult};
+use crate::material::Material;
+use crate::pdf::{random_float, solid_angle_pdf};
+
+pub struct Triangle<T: Float+MulAssign> {
+    pub v0: Vec3<T>,
+    pub v1: Vec3<T>,
+    pub v2: Vec3<T>,
+    pub material: Box<dyn Material<T>>,
+}
+
+/// Möller–Trumbore ray/triangle intersection, returns the ray parameter
+/// and the barycentric coordinates (u, v) of the hit point.
+pub fn intersect<T: Float+MulAssign>(v0: Vec3<T>, v1: Vec3<T>, v2: Vec3<T>, r: &Ray<T>, t_min: T, t_max: T) -> Option<(T, T, T)> {
+    let e1 = v1 - v0;
+    let e2 = v2 - v0;
+    let pvec = Vec3::cross(&r.direction, &e2);
+    let det = Vec3::dot(&e1, &pvec);
+    // ray parallel to the triangle plane. det scales with the area of the
+    // triangle and the length of the ray, so small triangles must not be
+    // compared against a fixed epsilon
+    if det == T::zero() {
+        return None;
+    }
+    let inv_det = T::one() / det;
+
+    let tvec = r.origin - v0;
+    let u = Vec3::dot(&tvec, &pvec) * inv_det;
+    if u < T::zero() || u > T::one() {
+        return None;
+    }
+
+    let qvec = Vec3::cross(&tvec, &e1);
+    let v = Vec3::dot(&r.direction, &qvec) * inv_det;
+    if v < T::zero() || u + v > T::one() {
+        return None;
+    }
+
+    let t = Vec3::dot(&e2, &qvec) * inv_det;
+    if t < t_max && t > t_min {
+        Some((t, u, v))
+    } else {
+        None
+    }
+}
+
+pub fn bounding_box<T: Float+MulAssign>(v0: Vec3<T>, v1: Vec3<T>, v2: Vec3<T>) -> Aabb<T> {
+    // pad flat boxes so that axis aligned triangles can still be hit
+    let pad = T::from(1e-4).unwrap();
+    let min = Vec3::min(&Vec3::min(&v0, &v1), &v2);
+    let max = Vec3::max(&Vec3::max(&v0, &v1), &v2);
+    let mut e = [T::zero(); 3];
+    for (a, e) in e.iter_mut().enumerate() {
+        if max[a] - min[a] < pad {
+            *e = pad;
+        }
+    }
+    let e = Vec3::new(Some(e));
+    Aabb::new(min - e, max + e)
+}
+
+impl<T: Float+MulAssign+Send+Sync> Hitable<T> for Triangle<T> {
+    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'_, T>> {
+        let (t, u, v) = intersect(self.v0, self.v1, self.v2, r, t_min, t_max)?;
+
+        Some(HitResult {
+            rec: HitRecord {
+                t,
+                p: r.point_at_parameter(t),
+                normal: Vec3::unit_vector(Vec3::cross(&(self.v1 - self.v0), &(self.v2 - self.v0))),
+                u,
+                v,
+                barycentric: Some([u, v]),
+            },
+            material: self.material.as_ref(),
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        Some(bounding_box(self.v0, self.v1, self.v2))
+    }
+
+    fn pdf_value(&self, origin: &Vec3<T>, direction: &Vec3<T>) -> T {
+        let r = Ray {
+            origin: *origin,
+            direction: *direction,
+            time: T::zero(),
+            wavelengths: None,
+        };
+        match intersect(self.v0, self.v1, self.v2, &r, T::from(0.001).unwrap(), T::max_value()) {
+            Some((t, _, _)) => {
+                let n = Vec3::cross(&(self.v1 - self.v0), &(self.v2 - self.v0));
+                let area = n.length() / (T::one() + T::one());
+                solid_angle_pdf(direction, t, &n, area)
+            },
+            None => T::zero(),
+        }
+    }
+
+    fn random(&self, origin: &Vec3<T>) -> Vec3<T> {
+        // uniform barycentric coordinates
+        let su = random_float::<T>().sqrt();
+        let b0 = T::one() - su;
+        let b1 = random_float::<T>() * su;
+        let p = self.v0 * b0 + self.v1 * b1 + self.v2 * (T::one() - b0 - b1);
+        p - *origin
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ray(origin: [f32; 3], direction: [f32; 3]) -> Ray<f32> {
+        Ray {
+            origin: Vec3::new(Some(origin)),
+            direction: Vec3::new(Some(direction)),
+            time: 0.0,
+            wavelengths: None,
+        }
+    }
+
+    #[test]
+    fn tiny_triangles_are_hit() {
+        // a micrometre sized triangle, det is about 1e-12
+        let s = 1e-6;
+        let (v0, v1, v2) = (
+            Vec3::new(Some([0.0, 0.0, 0.0])),
+            Vec3::new(Some([s, 0.0, 0.0])),
+            Vec3::new(Some([0.0, s, 0.0])),
+        );
+        let (t, u, v) = intersect(v0, v1, v2, &ray([0.25 * s, 0.5 * s, 1.0], [0.0, 0.0, -1.0]), 0.0, 10.0).unwrap();
+        assert!((t - 1.0).abs() < 1e-6);
+        assert!((u - 0.25).abs() < 1e-3 && (v - 0.5).abs() < 1e-3, "({}, {})", u, v);
+    }
+
+    #[test]
+    fn parallel_rays_miss() {
+        let (v0, v1, v2) = (
+            Vec3::new(Some([0.0, 0.0, 0.0])),
+            Vec3::new(Some([1.0, 0.0, 0.0])),
+            Vec3::new(Some([0.0, 1.0, 0.0])),
+        );
+        assert!(intersect(v0, v1, v2, &ray([-1.0, 0.2, 0.0], [1.0, 0.0, 0.0]), 0.0, 10.0).is_none());
+        assert!(intersect(v0, v1, v2, &ray([-1.0, 0.2, 0.5], [1.0, 0.0, 0.0]), 0.0, 10.0).is_none());
+    }
+}
@@ -0,0 +1,184 @@
+use std::ops::MulAssign;
+use std::sync::Arc;
+use num_traits::Float;
+
+use crate::vec3::Vec3;
+use crate::ray::Ray;
+use crate::aabb::Aabb;
+use crate::bvh::Bvh;
+use crate::hitable::{HitRecord, Hitable, HitResult};
+use crate::hitablelist::HitableList;
+use crate::material::Material;
+use crate::triangle;
+
+struct MeshData<T: Float+MulAssign> {
+    positions: Vec<Vec3<T>>,
+    normals: Option<Vec<Vec3<T>>>,
+    uvs: Option<Vec<[T; 2]>>,
+    indices: Vec<[usize; 3]>,
+    material: Box<dyn Material<T>>,
+}
+
+struct MeshTriangle<T: Float+MulAssign> {
+    mesh: Arc<MeshData<T>>,
+    index: usize,
+}
+
+/// Indexed triangle mesh. Normals and uvs, when given, are per vertex and
+/// are interpolated across each triangle. The triangles are kept in their
+/// own `Bvh`.
+pub struct TriangleMesh<T: Float+MulAssign> {
+    bvh: Bvh<T>,
+}
+
+impl<T: Float+MulAssign+Send+Sync+'static> TriangleMesh<T> {
+    pub fn new(
+        positions: Vec<Vec3<T>>,
+        normals: Option<Vec<Vec3<T>>>,
+        uvs: Option<Vec<[T; 2]>>,
+        indices: Vec<[usize; 3]>,
+        material: Box<dyn Material<T>>,
+    ) -> Result<TriangleMesh<T>, String> {
+        if let Some(i) = indices.iter().flatten().find(|&&i| i >= positions.len()) {
+            return Err(format!("vertex index {} out of range, the mesh has {} vertices", i, positions.len()));
+        }
+        if let Some(n) = normals.as_ref().filter(|n| n.len() != positions.len()) {
+            return Err(format!("expected one normal per vertex, found {} for {} vertices", n.len(), positions.len()));
+        }
+        if let Some(uv) = uvs.as_ref().filter(|uv| uv.len() != positions.len()) {
+            return Err(format!("expected one uv per vertex, found {} for {} vertices", uv.len(), positions.len()));
+        }
+
+        let num_triangles = indices.len();
+        let mesh = Arc::new(MeshData {
+            positions,
+            normals,
+            uvs,
+            indices,
+            material,
+        });
+
+        let mut triangles: HitableList<T> = HitableList {
+            list: Vec::with_capacity(num_triangles)
+        };
+        for index in 0..num_triangles {
+            triangles.list.push(Box::new(MeshTriangle {
+                mesh: mesh.clone(),
+                index,
+            }));
+        }
+
+        Ok(TriangleMesh {
+            bvh: Bvh::new(triangles),
+        })
+    }
+}
+
+impl<T: Float+MulAssign> MeshTriangle<T> {
+    fn vertices(&self) -> (Vec3<T>, Vec3<T>, Vec3<T>) {
+        let [i0, i1, i2] = self.mesh.indices[self.index];
+        (self.mesh.positions[i0], self.mesh.positions[i1], self.mesh.positions[i2])
+    }
+}
+
+impl<T: Float+MulAssign+Send+Sync> Hitable<T> for MeshTriangle<T> {
+    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'_, T>> {
+        let (v0, v1, v2) = self.vertices();
+        let (t, b1, b2) = triangle::intersect(v0, v1, v2, r, t_min, t_max)?;
+        let b0 = T::one() - b1 - b2;
+        let [i0, i1, i2] = self.mesh.indices[self.index];
+
+        let normal = match &self.mesh.normals {
+            Some(n) => Vec3::unit_vector(n[i0] * b0 + n[i1] * b1 + n[i2] * b2),
+            None => Vec3::unit_vector(Vec3::cross(&(v1 - v0), &(v2 - v0))),
+        };
+        // texture coordinates take the place of the barycentric ones in u
+        // and v, which stay available in `barycentric`
+        let (u, v) = match &self.mesh.uvs {
+            Some(uv) => (
+                uv[i0][0] * b0 + uv[i1][0] * b1 + uv[i2][0] * b2,
+                uv[i0][1] * b0 + uv[i1][1] * b1 + uv[i2][1] * b2,
+            ),
+            None => (b1, b2),
+        };
+
+        Some(HitResult {
+            rec: HitRecord {
+                t,
+                p: r.point_at_parameter(t),
+                normal,
+                u,
+                v,
+                barycentric: Some([b1, b2]),
+            },
+            material: self.mesh.material.as_ref(),
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        let (v0, v1, v2) = self.vertices();
+        Some(triangle::bounding_box(v0, v1, v2))
+    }
+}
+
+impl<T: Float+MulAssign+Send+Sync> Hitable<T> for TriangleMesh<T> {
+    fn hit(&self, r: &Ray<T>, t_min: T, t_max: T) -> Option<HitResult<'_, T>> {
+        self.bvh.hit(r, t_min, t_max)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb<T>> {
+        self.bvh.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+    use crate::texture::ConstantTexture;
+
+    #[test]
+    fn texture_coordinates_keep_the_barycentrics() {
+        let positions = vec![
+            Vec3::new(Some([0.0, 0.0, 0.0])),
+            Vec3::new(Some([1.0, 0.0, 0.0])),
+            Vec3::new(Some([0.0, 1.0, 0.0])),
+        ];
+        let uvs = vec![[0.5, 0.5], [1.0, 0.5], [0.5, 1.0]];
+        let material = Box::new(Lambertian {
+            albedo: Box::new(ConstantTexture { color: Vec3::new(Some([0.5, 0.5, 0.5])) }),
+        });
+        let mesh = TriangleMesh::new(positions, None, Some(uvs), vec![[0, 1, 2]], material).unwrap();
+
+        let r = Ray {
+            origin: Vec3::new(Some([0.2, 0.4, 1.0])),
+            direction: Vec3::new(Some([0.0, 0.0, -1.0])),
+            time: 0.0,
+            wavelengths: None,
+        };
+        let rec = mesh.hit(&r, 0.0, 10.0).unwrap().rec;
+        let [b1, b2] = rec.barycentric.unwrap();
+        assert!((b1 - 0.2).abs() < 1e-6 && (b2 - 0.4).abs() < 1e-6);
+        assert!((rec.u - 0.6).abs() < 1e-6 && (rec.v - 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn malformed_meshes_are_errors() {
+        let material = || -> Box<dyn Material<f32>> {
+            Box::new(Lambertian {
+                albedo: Box::new(ConstantTexture { color: Vec3::new(None) }),
+            })
+        };
+        let positions = vec![Vec3::new(None); 3];
+        let error = |r: Result<TriangleMesh<f32>, String>| r.err().unwrap();
+        assert_eq!(
+            error(TriangleMesh::new(positions.clone(), None, None, vec![[0, 1, 3]], material())),
+            "vertex index 3 out of range, the mesh has 3 vertices");
+        assert_eq!(
+            error(TriangleMesh::new(positions.clone(), Some(vec![Vec3::new(None); 2]), None, vec![[0, 1, 2]], material())),
+            "expected one normal per vertex, found 2 for 3 vertices");
+        assert_eq!(
+            error(TriangleMesh::new(positions, None, Some(vec![[0.0, 0.0]; 4]), vec![[0, 1, 2]], material())),
+            "expected one uv per vertex, found 4 for 3 vertices");
+    }
+}
@@ -6,9 +6,10 @@ use std::ops::{
     Mul, MulAssign,
     Index
 };
-use rand::Rng;
 use num_traits::Float;
 
+use crate::random;
+
 type InternalVec3<T> = [T; 3];
 
 #[derive(Copy,Clone)]
@@ -25,16 +26,15 @@ impl<T: Float + MulAssign> Vec3<T> {
     }
 
     pub fn random_in_unit_sphere() -> Vec3<T> {
-        let mut rng = rand::thread_rng();
         let one = T::one();
         let two = T::one() + T::one();
         let vec3_unit = Vec3::new(Some([one, one, one]));
     
         loop {
             let iv = [
-                T::from(rng.gen::<f32>()),
-                T::from(rng.gen::<f32>()),
-                T::from(rng.gen::<f32>()),
+                T::from(random::gen::<f32>()),
+                T::from(random::gen::<f32>()),
+                T::from(random::gen::<f32>()),
             ];
             if iv[0].is_none() || iv[1].is_none() || iv[2].is_none() {
                 continue;
@@ -99,12 +99,24 @@ impl<T: Float + MulAssign> Vec3<T> {
         }
     }
 
+    pub fn min(a: &Vec3<T>, b: &Vec3<T>) -> Vec3<T> {
+        Vec3 {
+            e: [a.e[0].min(b.e[0]), a.e[1].min(b.e[1]), a.e[2].min(b.e[2])]
+        }
+    }
+
+    pub fn max(a: &Vec3<T>, b: &Vec3<T>) -> Vec3<T> {
+        Vec3 {
+            e: [a.e[0].max(b.e[0]), a.e[1].max(b.e[1]), a.e[2].max(b.e[2])]
+        }
+    }
+
     pub fn length(&self) -> T {
         (self.e[0]*self.e[0] + self.e[1]*self.e[1] + self.e[2]*self.e[2]).sqrt()
     }
 
     pub fn squared_length(&self) -> T {
-        (self.e[0]*self.e[0] + self.e[1]*self.e[1] + self.e[2]*self.e[2])
+        self.e[0]*self.e[0] + self.e[1]*self.e[1] + self.e[2]*self.e[2]
     }
 
     pub fn unit_vector(v: Vec3<T>) -> Vec3<T> {